@@ -11,7 +11,6 @@ use std::{
     path::{Path, PathBuf},
     time::Duration,
 };
-use zerocopy::FromBytes;
 
 use attest_data::{
     messages::{HostToRotCommand, RotToHost},
@@ -296,21 +295,8 @@ impl Worker {
             if data_size.is_none()
                 && data.len() >= std::mem::size_of::<BootSpHeader>()
             {
-                let (header, _) = BootSpHeader::ref_from_prefix(&data).unwrap();
+                let header = BootSpHeader::from_bytes(&data)?;
                 info!(self.log, "got boot header");
-                if header.magic != BootSpHeader::MAGIC {
-                    bail!(
-                        "invalid header magic: expected {:#x}, got {:#x}",
-                        BootSpHeader::MAGIC,
-                        header.magic
-                    );
-                } else if header.version != BootSpHeader::VERSION {
-                    bail!(
-                        "invalid header version: expected {:#x}, got {:#x}",
-                        BootSpHeader::VERSION,
-                        header.version
-                    );
-                }
                 info!(self.log, "  flags:        {:#x}", header.flags);
                 info!(self.log, "  data size:    {:#x}", header.data_size);
                 info!(self.log, "  image size:   {:#x}", header.image_size);
@@ -320,24 +306,8 @@ impl Worker {
                     "  sha256:       {}",
                     hex::encode(header.sha256)
                 );
-                info!(
-                    self.log,
-                    "  dataset name: {}",
-                    if let Ok(s) = std::str::from_utf8(&header.dataset) {
-                        s.to_string()
-                    } else {
-                        format!("{:02x?}", header.dataset)
-                    }
-                );
-                info!(
-                    self.log,
-                    "  image name:   {}",
-                    if let Ok(s) = std::str::from_utf8(&header.imagename) {
-                        s.to_string()
-                    } else {
-                        format!("{:02x?}", header.imagename)
-                    }
-                );
+                info!(self.log, "  dataset name: {}", header.dataset_str());
+                info!(self.log, "  image name:   {}", header.imagename_str());
 
                 let size = header.data_size + BootSpHeader::HEADER_SIZE as u64;
                 debug!(self.log, "setting image size to {size}");
@@ -0,0 +1,16 @@
+//
+// Feeds arbitrary bytes to the panic data decoder, which must return either
+// panic data or an error -- but never panic.  The fixtures in tests/fixtures
+// can be given as an additional corpus directory to start from something
+// plausible:
+//
+//     cargo fuzz run panic_data tests/fixtures
+//
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ipcc_data::PanicData::from_bytes(data.to_vec());
+});
@@ -8,23 +8,167 @@
 //! over the inter-processor communications channel (IPCC).  (See [RFD 316]
 //! for details of this channel and its mechanics.) Because this crate is
 //! designed *only* for those payloads that are opaque to the SP (e.g.,
-//! `HSSBootFail`, `HSSPanic`), it is not a `no-std` crate:  it is not
-//! designed to be used *in situ* by the SP, but rather in higher level
-//! software that must interpret IPCC data payloads -- which is to say, the
-//! control plane and [Humility].  For the host-side definitions of the IPCC
-//! payloads that this crate interprets, see [`kernel_ipcc.h`].
+//! `HSSBootFail`, `HSSPanic`), it is not designed to be used *in situ* by
+//! the SP, but rather in higher level software that must interpret IPCC data
+//! payloads -- which is to say, the control plane and [Humility].  For the
+//! host-side definitions of the IPCC payloads that this crate interprets,
+//! see [`kernel_ipcc.h`].
+//!
+//! The `std` feature is enabled by default; the crate can be built without
+//! it (though with `alloc`) for environments that lack `std` but must still
+//! decode payloads.  Without `std`, the `Read`-, file- and `SystemTime`-based
+//! interfaces are unavailable, as is the `decompress` feature.
+//!
+//! If the `serde` feature is enabled, [`PanicData`] and the types that it
+//! is composed of implement `Serialize` and `Deserialize`; decoded registers
+//! are keyed by their name (e.g., `"rdi"`).
+//!
+//! If the `verify` feature is enabled, `BootSpHeader::verify_image` can be
+//! used to check a boot image against the digest in its header; if the
+//! `decompress` feature is enabled, `BootSpHeader::decompress_image` can be
+//! used to inflate a compressed boot image.  If the `chrono` feature is
+//! enabled, `AdjustedTime::to_datetime` converts a host time to a
+//! `chrono::DateTime`.  If the `base64` feature is enabled,
+//! `PanicData::from_base64` decodes a panic payload encoded as base64.
+//! If the `json` feature is enabled, `PanicData::to_json_value` renders panic
+//! data as a `serde_json::Value` (without requiring the `serde` feature).
+//! If the `tracing` feature is enabled, the fixups for hubris#1554 (and the
+//! inference of the version that they require) emit `tracing` events at
+//! `debug` and `trace` levels.
 //!
 //! [RFD 316]: https://rfd.shared.oxide.computer/rfd/0316
 //! [Humility]: https://github.com/oxidecomputer/humility
 //! [`kernel_ipcc.h`]: https://github.com/oxidecomputer/illumos-gate/blob/stlouis/usr/src/uts/oxide/sys/kernel_ipcc.h
 //!
 
-use anyhow::{bail, Context, Result};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
 use binrw::helpers::until_eof;
-use binrw::{io::Cursor, BinRead};
+use binrw::{io::Cursor, BinRead, BinWrite, Endian};
+use core::convert::TryFrom;
 use derive_more::{Display, LowerHex};
 use indexmap::IndexMap;
-use std::convert::{TryFrom, TryInto};
+use thiserror::Error;
+use zerocopy::FromBytes;
+
+/// An error in interpreting an IPCC data payload.
+#[derive(Error, Debug)]
+pub enum IpccDataError {
+    #[cfg(feature = "std")]
+    #[error("failed to read panic data")]
+    Read(#[source] std::io::Error),
+    #[cfg(feature = "std")]
+    #[error("failed to read panic data from {}", path.display())]
+    ReadFile {
+        path: std::path::PathBuf,
+        #[source]
+        err: std::io::Error,
+    },
+    #[error("invalid hex in panic data: {0:?}")]
+    InvalidHex(String),
+    #[cfg(feature = "base64")]
+    #[error("invalid base64 in panic data")]
+    InvalidBase64(#[cfg_attr(feature = "std", source)] base64::DecodeError),
+    #[error("panic payload too short: got {got} bytes, need at least {need}")]
+    PayloadTooShort { got: usize, need: usize },
+    #[error("payload is empty or entirely zero")]
+    EmptyPayload,
+    #[error("could not decode `ipd_cause`: {0:#04x}")]
+    UnrecognizedCause(u8),
+    #[error("failed to deserialize panic data to infer version")]
+    InferVersion(#[cfg_attr(feature = "std", source)] binrw::Error),
+    #[error("failed to deserialize panic data")]
+    Deserialize(#[cfg_attr(feature = "std", source)] binrw::Error),
+    #[error("failed to serialize panic data")]
+    Serialize(#[cfg_attr(feature = "std", source)] binrw::Error),
+    #[error("{field} too large to serialize: {got} exceeds {:#x}", u16::MAX)]
+    SerializeOverflow { field: &'static str, got: usize },
+    #[error("inconsistent panic stack: {0}")]
+    StackInconsistent(&'static str),
+    #[error("failed to deserialize item {item}")]
+    DeserializeItem {
+        item: String,
+        #[cfg_attr(feature = "std", source)]
+        err: binrw::Error,
+    },
+    #[error(
+        "items length mismatch: header says {expected}, items total {got}"
+    )]
+    ItemsLengthMismatch { expected: u16, got: usize },
+    #[error("implausible item count {nitems} for {len} bytes of items")]
+    ImplausibleItemCount { nitems: u16, len: usize },
+    #[error("item {index} has invalid length {len} (minimum is 3)")]
+    InvalidItemLength { index: usize, len: u16 },
+    #[error("failed to decode message: {0:#x?}")]
+    InvalidMessage(Vec<u8>),
+    #[error("failed to decode symbol: {0:#x?}")]
+    InvalidSymbol(Vec<u8>),
+    #[error("panic data version {0} was inferred rather than determined")]
+    VersionNotDetermined(u8),
+    #[error("illegal nsec value {0}")]
+    IllegalNsec(u64),
+    #[error("time out of range: {sec}.{nsec:09}")]
+    TimeOutOfRange { sec: u64, nsec: u32 },
+    #[error("unknown panic cause: {0}")]
+    UnknownPanicCause(String),
+    #[error("unknown register: {0}")]
+    UnknownRegister(String),
+    #[error("unsupported IPCC panic data version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("boot failure payload too long: got {got} bytes, max is {max}")]
+    BootFailTooLong { got: usize, max: usize },
+    #[error("failed to deserialize boot failure data")]
+    BootFailDeserialize(#[cfg_attr(feature = "std", source)] binrw::Error),
+    #[error("boot header too short: got {got} bytes, need at least {need}")]
+    BootSpHeaderTooShort { got: usize, need: usize },
+    #[error(
+        "invalid header magic: expected {:#x}, got {:#x}",
+        BootSpHeader::MAGIC,
+        .0
+    )]
+    BootSpHeaderBadMagic(u32),
+    #[error(
+        "invalid header version: expected {:#x}, got {:#x}",
+        BootSpHeader::VERSION,
+        .0
+    )]
+    BootSpHeaderBadVersion(u32),
+    #[error("header {field} too long: got {got} bytes, max {max}")]
+    BootSpHeaderFieldTooLong {
+        field: &'static str,
+        got: usize,
+        max: usize,
+    },
+    #[error("inconsistent header sizes: {0}")]
+    BootSpHeaderInconsistent(&'static str),
+    #[error("image size mismatch: expected {expected} bytes, got {got}")]
+    ImageSizeMismatch { expected: u64, got: usize },
+    #[cfg(feature = "verify")]
+    #[error(
+        "image digest mismatch: expected {}, computed {}",
+        hex_digest(.expected),
+        hex_digest(.computed)
+    )]
+    ImageDigestMismatch {
+        expected: [u8; 32],
+        computed: [u8; 32],
+    },
+    #[cfg(feature = "decompress")]
+    #[error("failed to decompress image")]
+    Decompress(#[source] std::io::Error),
+}
+
+#[cfg(feature = "verify")]
+fn hex_digest(digest: &[u8; 32]) -> String {
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
 
 /// The version of the IPCC panic data -- and an indicator of whether this
 /// version was able to be determined from the data directly, or had to be
@@ -32,7 +176,8 @@ use std::convert::{TryFrom, TryInto};
 ///
 /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
 ///
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PanicDataVersion {
     /// Version was determined
     Determined(u8),
@@ -42,16 +187,26 @@ pub enum PanicDataVersion {
 }
 
 impl PanicDataVersion {
-    fn number(&self) -> u8 {
+    /// Returns the version number, whether determined or inferred.
+    pub fn number(&self) -> u8 {
         match self {
             PanicDataVersion::Determined(v) => *v,
             PanicDataVersion::Inferred(v) => *v,
         }
     }
+
+    /// Returns true if the version had to be inferred, indicating that the
+    /// payload suffered from [hubris#1554].
+    ///
+    /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+    ///
+    pub fn is_inferred(&self) -> bool {
+        matches!(self, PanicDataVersion::Inferred(_))
+    }
 }
 
-impl std::fmt::Display for PanicDataVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl core::fmt::Display for PanicDataVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{} ({})",
@@ -65,32 +220,149 @@ impl std::fmt::Display for PanicDataVersion {
 }
 
 /// A host CPU identifier
-#[derive(Debug, Display)]
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cpuid(pub u32);
 
+impl Cpuid {
+    /// Returns true if the CPU ID is no larger than [`MAX_PLAUSIBLE_CPUID`],
+    /// the threshold used when inferring the version of panic data.
+    pub fn is_plausible(&self) -> bool {
+        self.0 <= MAX_PLAUSIBLE_CPUID
+    }
+}
+
 /// A host CPU memory address
-#[derive(Debug, Display, LowerHex)]
+#[derive(Copy, Clone, Debug, Display, LowerHex, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Addr(pub u64);
 
+impl Addr {
+    /// Returns true if the address is in canonical form (presuming 48-bit
+    /// virtual addresses) -- that is, if bits 63 through 47 are all equal.
+    /// A non-canonical address cannot have been dereferenced, and is likely
+    /// a corrupt or wild pointer.
+    pub fn is_canonical(&self) -> bool {
+        matches!(self.0 >> 47, 0 | 0x1_ffff)
+    }
+
+    /// Returns true if the address is a canonical address in the upper half
+    /// of the address space, in which the kernel resides.
+    pub fn is_kernel(&self) -> bool {
+        self.0 >> 47 == 0x1_ffff
+    }
+
+    /// Returns true if the address is a canonical address in the lower half
+    /// of the address space (that is, a user address).
+    pub fn is_user(&self) -> bool {
+        self.0 >> 47 == 0
+    }
+}
+
 /// Host time, in adjusted time (that is, seconds and nanoseconds since the
 /// Epoch). Note that if this was gathered during boot, its correctness with
 /// respect to true wall time will depend on when it was gathered:  if very
 /// early in boot, it will be in 1970; if less early but still before any time
 /// synchronization has started, it will be like an aging X'er: trapped in the
 /// 1980s.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AdjustedTime {
     pub sec: u64,
     pub nsec: u32,
 }
 
+impl AdjustedTime {
+    /// The time (in seconds since the Epoch) before which a host time is
+    /// deemed to be suspect: 2023-01-01T00:00:00Z, which predates any Oxide
+    /// system in the field.
+    pub const SUSPECT_BEFORE: u64 = 1_672_531_200;
+
+    /// Returns true if the time predates [`AdjustedTime::SUSPECT_BEFORE`],
+    /// in which case it was very likely gathered before time was
+    /// synchronized (and is therefore not to be trusted).
+    pub fn is_suspect(&self) -> bool {
+        self.is_suspect_before(Self::SUSPECT_BEFORE)
+    }
+
+    /// Like [`AdjustedTime::is_suspect`], but with a caller-specified
+    /// threshold (in seconds since the Epoch).
+    pub fn is_suspect_before(&self, sec: u64) -> bool {
+        self.sec < sec
+    }
+
+    #[cfg(any(feature = "std", feature = "chrono"))]
+    fn check_nsec(&self) -> Result<(), IpccDataError> {
+        if self.nsec >= 1_000_000_000 {
+            return Err(IpccDataError::IllegalNsec(u64::from(self.nsec)));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(any(feature = "std", feature = "chrono"))]
+    fn out_of_range(&self) -> IpccDataError {
+        IpccDataError::TimeOutOfRange {
+            sec: self.sec,
+            nsec: self.nsec,
+        }
+    }
+
+    /// Converts the time to a [`chrono::DateTime`].
+    #[cfg(feature = "chrono")]
+    pub fn to_datetime(
+        &self,
+    ) -> Result<chrono::DateTime<chrono::Utc>, IpccDataError> {
+        self.check_nsec()?;
+
+        let sec = i64::try_from(self.sec).map_err(|_| self.out_of_range())?;
+
+        chrono::DateTime::from_timestamp(sec, self.nsec)
+            .ok_or_else(|| self.out_of_range())
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<&AdjustedTime> for std::time::SystemTime {
+    type Error = IpccDataError;
+
+    fn try_from(time: &AdjustedTime) -> Result<Self, Self::Error> {
+        time.check_nsec()?;
+
+        std::time::UNIX_EPOCH
+            .checked_add(std::time::Duration::new(time.sec, time.nsec))
+            .ok_or_else(|| time.out_of_range())
+    }
+}
+
 /// Host time, in monotonically increasing nanoseconds.
-#[derive(Debug, Display)]
+#[derive(Copy, Clone, Debug, Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MonotonicNanoseconds(pub u64);
 
+impl MonotonicNanoseconds {
+    /// Returns the time since boot as a [`Duration`](core::time::Duration).
+    pub fn as_duration(&self) -> core::time::Duration {
+        core::time::Duration::from_nanos(self.0)
+    }
+}
+
+//
+// As with `std::time::Instant`, subtracting a later time from an earlier one
+// saturates to zero rather than panicking.
+//
+impl core::ops::Sub for MonotonicNanoseconds {
+    type Output = core::time::Duration;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        core::time::Duration::from_nanos(self.0.saturating_sub(rhs.0))
+    }
+}
+
 /// A stack frame, consisting of a program text address of the caller.  This
 /// is also expressed as a symbol and offset, if present.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StackFrame {
     /// Address of caller
     pub address: Addr,
@@ -102,9 +374,34 @@ pub struct StackFrame {
     pub offset: u64,
 }
 
+/// Symbols (and offsets) for the addresses in panic data other than those of
+/// its stack, as resolved by [`PanicData::symbolize`].  Of these, only the
+/// program counter is a text address and is always meaningfully
+/// symbolizable; the thread is a kernel data address that will only resolve
+/// to a symbol for statically allocated threads (e.g., `t0`), while the frame
+/// pointer and register pointer point into the stack and will generally not
+/// resolve to anything.
+#[derive(Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddrSymbols {
+    /// symbol of panicking thread, if known
+    pub thread: Option<(String, u64)>,
+
+    /// symbol of program counter, if known
+    pub pc: Option<(String, u64)>,
+
+    /// symbol of frame pointer, if known
+    pub fp: Option<(String, u64)>,
+
+    /// symbol of pointer to panic registers, if known
+    pub rp: Option<(String, u64)>,
+}
+
 /// A host register (presuming an AMD64 host).
-#[derive(Debug, Hash, Eq, PartialEq, Display)]
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Display)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
+#[non_exhaustive]
 pub enum Register {
     rdi,
     rsi,
@@ -136,8 +433,93 @@ pub enum Register {
     ss,
 }
 
-impl std::fmt::Display for StackFrame {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Register {
+    /// Returns all registers, in the order in which they are displayed by
+    /// dumpregs() in the operating system.
+    pub fn all() -> &'static [Register] {
+        &[
+            Register::rdi,
+            Register::rsi,
+            Register::rdx,
+            Register::rcx,
+            Register::r8,
+            Register::r9,
+            Register::rax,
+            Register::rbx,
+            Register::rbp,
+            Register::r10,
+            Register::r11,
+            Register::r12,
+            Register::r13,
+            Register::r14,
+            Register::r15,
+            Register::fsbase,
+            Register::gsbase,
+            Register::ds,
+            Register::es,
+            Register::fs,
+            Register::gs,
+            Register::trapno,
+            Register::err,
+            Register::rip,
+            Register::cs,
+            Register::rfl,
+            Register::rsp,
+            Register::ss,
+        ]
+    }
+
+    /// Returns the DWARF register number for this register, as defined by
+    /// the AMD64 System V psABI -- or `None` if the register has no DWARF
+    /// register number.  Per convention, `%rip` is mapped to the return
+    /// address column.
+    pub fn dwarf_number(&self) -> Option<u16> {
+        match self {
+            Register::rax => Some(0),
+            Register::rdx => Some(1),
+            Register::rcx => Some(2),
+            Register::rbx => Some(3),
+            Register::rsi => Some(4),
+            Register::rdi => Some(5),
+            Register::rbp => Some(6),
+            Register::rsp => Some(7),
+            Register::r8 => Some(8),
+            Register::r9 => Some(9),
+            Register::r10 => Some(10),
+            Register::r11 => Some(11),
+            Register::r12 => Some(12),
+            Register::r13 => Some(13),
+            Register::r14 => Some(14),
+            Register::r15 => Some(15),
+            Register::rip => Some(16),
+            Register::rfl => Some(49),
+            Register::es => Some(50),
+            Register::cs => Some(51),
+            Register::ss => Some(52),
+            Register::ds => Some(53),
+            Register::fs => Some(54),
+            Register::gs => Some(55),
+            Register::fsbase => Some(58),
+            Register::gsbase => Some(59),
+            Register::trapno | Register::err => None,
+        }
+    }
+}
+
+impl core::str::FromStr for Register {
+    type Err = IpccDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Register::all()
+            .iter()
+            .find(|r| r.to_string() == s)
+            .copied()
+            .ok_or_else(|| IpccDataError::UnknownRegister(s.to_string()))
+    }
+}
+
+impl core::fmt::Display for StackFrame {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let width = f.width().unwrap_or(0);
 
         write!(
@@ -151,8 +533,86 @@ impl std::fmt::Display for StackFrame {
     }
 }
 
+impl StackFrame {
+    /// Creates a frame with the given address, symbol (if known) and offset
+    /// from that symbol.  Frames that lack either an address or a symbol can
+    /// be more conveniently created with [`StackFrame::symbolic`] and
+    /// [`StackFrame::raw`], respectively:
+    ///
+    /// ```
+    /// use ipcc_data::{Addr, StackFrame};
+    ///
+    /// let addr = Addr(0xfffffffffbc3a1d0);
+    /// let frame = StackFrame::new(addr, Some("die".to_string()), 0x43);
+    /// assert_eq!(frame.to_string(), "die+0x43");
+    ///
+    /// let frame = StackFrame::symbolic("die", 0x43);
+    /// assert_eq!(frame.address, Addr(0));
+    /// assert_eq!(frame.to_string(), "die+0x43");
+    ///
+    /// let frame = StackFrame::raw(addr);
+    /// assert!(!frame.is_symbolicated());
+    /// assert_eq!(frame.to_string(), "0xfffffffffbc3a1d0");
+    /// ```
+    pub fn new(address: Addr, symbol: Option<String>, offset: u64) -> Self {
+        Self {
+            address,
+            symbol,
+            offset,
+        }
+    }
+
+    /// Creates a frame with a symbol and offset, but no address.
+    pub fn symbolic(symbol: &str, offset: u64) -> Self {
+        Self::new(Addr(0), Some(symbol.to_string()), offset)
+    }
+
+    /// Creates a frame with an address, but no symbol.
+    pub fn raw(address: Addr) -> Self {
+        Self::new(address, None, 0)
+    }
+
+    /// Returns true if the frame has a symbol.
+    pub fn is_symbolicated(&self) -> bool {
+        self.symbol.is_some()
+    }
+
+    //
+    // Frames match if they have the same symbol and offset -- or, if either
+    // lacks a symbol, the same address.
+    //
+    fn matches(&self, other: &StackFrame) -> bool {
+        match (&self.symbol, &other.symbol) {
+            (Some(l), Some(r)) => l == r && self.offset == other.offset,
+            _ => self.address.0 == other.address.0,
+        }
+    }
+}
+
+/// A run of identical stack frames, as returned by
+/// [`PanicData::collapsed_stack`].
+#[derive(Debug)]
+pub struct CollapsedFrame<'a> {
+    /// the frame
+    pub frame: &'a StackFrame,
+
+    /// the number of consecutive times the frame appears in the stack
+    pub repeat: usize,
+}
+
+impl core::fmt::Display for CollapsedFrame<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.repeat {
+            1 => write!(f, "{}", self.frame),
+            repeat => write!(f, "{} (x{})", self.frame, repeat),
+        }
+    }
+}
+
 /// The cause of a panic.
-#[derive(Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum PanicCause {
     /// Explicit call to panic
     Call,
@@ -175,6 +635,10 @@ pub enum PanicCause {
     /// Panic early in boot due to unknown cause because of missing LSB
     EarlyBootUnknown,
 
+    /// Panic early in boot due to an unrecognized cause, as indicated by the
+    /// given LSB (that is, the cause is `0xeb` followed by this byte)
+    EarlyBootOther(u8),
+
     /// Corrupt or otherwise unrecognized cause
     Unknown(u16),
 }
@@ -189,13 +653,127 @@ impl From<u16> for PanicCause {
             0xeb97 => Self::EarlyBootPROM,
             0xeba9 => Self::EarlyBootTrap,
             0xebff => Self::EarlyBootUnknown,
+            c if c >> 8 == 0xeb => Self::EarlyBootOther(c as u8),
             c => Self::Unknown(c),
         }
     }
 }
 
-impl std::fmt::Display for PanicCause {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl PanicCause {
+    /// Returns the value of the cause as it appears on the wire.
+    pub fn wire_value(&self) -> u16 {
+        match self {
+            Self::Call => 0xca11,
+            Self::Trap => 0xa900,
+            Self::UserTrap => 0x5e00,
+            Self::EarlyBoot => 0xeb00,
+            Self::EarlyBootPROM => 0xeb97,
+            Self::EarlyBootTrap => 0xeba9,
+            Self::EarlyBootUnknown => 0xebff,
+            Self::EarlyBootOther(subtype) => 0xeb00 | u16::from(*subtype),
+            Self::Unknown(c) => *c,
+        }
+    }
+
+    /// Returns the identifier of the cause as defined in [`kernel_ipcc.h`]
+    /// (e.g., `"IPCC_PANIC_TRAP"`), or `None` if the cause has no such
+    /// identifier (that is, if it is unknown or could not be determined).
+    /// Unlike the display of the cause, this is only ever a C identifier.
+    ///
+    /// [`kernel_ipcc.h`]: https://github.com/oxidecomputer/illumos-gate/blob/stlouis/usr/src/uts/oxide/sys/kernel_ipcc.h
+    pub fn c_identifier(&self) -> Option<&'static str> {
+        match self {
+            Self::Call => Some("IPCC_PANIC_CALL"),
+            Self::Trap => Some("IPCC_PANIC_TRAP"),
+            Self::UserTrap => Some("IPCC_PANIC_USERTRAP"),
+            Self::EarlyBoot => Some("IPCC_PANIC_EARLYBOOT"),
+            Self::EarlyBootPROM => Some("IPCC_PANIC_EARLYBOOT_PROM"),
+            Self::EarlyBootTrap => Some("IPCC_PANIC_EARLYBOOT_TRAP"),
+            Self::EarlyBootUnknown
+            | Self::EarlyBootOther(_)
+            | Self::Unknown(_) => None,
+        }
+    }
+
+    /// For a panic early in boot, returns the LSB of the cause that denotes
+    /// the flavor of early boot panic (e.g., `0x97` for a call to
+    /// prom_panic()), allowing early boot causes to be distinguished even
+    /// when they are not otherwise recognized.  Returns `None` if the panic
+    /// was not early in boot, or if the LSB is missing due to hubris#1554.
+    pub fn early_boot_subtype(&self) -> Option<u8> {
+        match self {
+            Self::EarlyBootUnknown => None,
+            c if c.is_early_boot() => Some(c.wire_value() as u8),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the panic occurred early in boot.
+    pub fn is_early_boot(&self) -> bool {
+        matches!(
+            self,
+            Self::EarlyBoot
+                | Self::EarlyBootPROM
+                | Self::EarlyBootTrap
+                | Self::EarlyBootUnknown
+                | Self::EarlyBootOther(_)
+        )
+    }
+
+    /// Returns true if the panic was due to a trap of any flavor.  Note that
+    /// this includes a trap early in boot, which is also an early boot
+    /// cause.
+    pub fn is_trap(&self) -> bool {
+        matches!(self, Self::Trap | Self::UserTrap | Self::EarlyBootTrap)
+    }
+
+    /// Returns true if the panic was due to an explicit call to panic.
+    pub fn is_explicit(&self) -> bool {
+        matches!(self, Self::Call)
+    }
+}
+
+//
+// We accept the names as displayed (including that of an unrecognized early
+// boot cause), as well as a hexadecimal wire value (e.g., `0xca11`).
+//
+impl core::str::FromStr for PanicCause {
+    type Err = IpccDataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "IPCC_PANIC_CALL" => Ok(Self::Call),
+            "IPCC_PANIC_TRAP" => Ok(Self::Trap),
+            "IPCC_PANIC_USERTRAP" => Ok(Self::UserTrap),
+            "IPCC_PANIC_EARLYBOOT" => Ok(Self::EarlyBoot),
+            "IPCC_PANIC_EARLYBOOT_PROM" => Ok(Self::EarlyBootPROM),
+            "IPCC_PANIC_EARLYBOOT_TRAP" => Ok(Self::EarlyBootTrap),
+            "IPCC_PANIC_EARLYBOOT_*" => Ok(Self::EarlyBootUnknown),
+            _ => {
+                let unknown =
+                    || IpccDataError::UnknownPanicCause(s.to_string());
+
+                if let Some(lsb) = s
+                    .strip_prefix("<Unknown early boot cause 0x")
+                    .and_then(|lsb| lsb.strip_suffix('>'))
+                {
+                    u8::from_str_radix(lsb, 16)
+                        .map(|lsb| Self::from(0xeb00 | u16::from(lsb)))
+                        .map_err(|_| unknown())
+                } else if let Some(hex) = s.strip_prefix("0x") {
+                    u16::from_str_radix(hex, 16)
+                        .map(Self::from)
+                        .map_err(|_| unknown())
+                } else {
+                    Err(unknown())
+                }
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for PanicCause {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{}",
@@ -207,19 +785,348 @@ impl std::fmt::Display for PanicCause {
                 Self::EarlyBootPROM => "IPCC_PANIC_EARLYBOOT_PROM".to_owned(),
                 Self::EarlyBootTrap => "IPCC_PANIC_EARLYBOOT_TRAP".to_owned(),
                 Self::EarlyBootUnknown => "IPCC_PANIC_EARLYBOOT_*".to_owned(),
+                Self::EarlyBootOther(subtype) => {
+                    format!("<Unknown early boot cause {subtype:#04x}>")
+                }
                 Self::Unknown(c) => format!("<Unknown cause {c:#06x}>"),
             }
         )
     }
 }
 
+/// An AMD64 trap, as indicated by the `trapno` register.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Trap {
+    /// #DE: divide error
+    DivideError,
+
+    /// #DB: debug exception
+    Debug,
+
+    /// NMI: non-maskable interrupt
+    Nmi,
+
+    /// #BP: breakpoint
+    Breakpoint,
+
+    /// #OF: overflow
+    Overflow,
+
+    /// #BR: bound range exceeded
+    BoundRange,
+
+    /// #UD: invalid opcode
+    InvalidOpcode,
+
+    /// #NM: device not available
+    DeviceNotAvailable,
+
+    /// #DF: double fault
+    DoubleFault,
+
+    /// coprocessor segment overrun
+    CoprocessorOverrun,
+
+    /// #TS: invalid TSS
+    InvalidTss,
+
+    /// #NP: segment not present
+    SegmentNotPresent,
+
+    /// #SS: stack fault
+    StackFault,
+
+    /// #GP: general protection
+    GeneralProtection,
+
+    /// #PF: page fault
+    PageFault,
+
+    /// #MF: x87 floating-point exception
+    FloatingPoint,
+
+    /// #AC: alignment check
+    AlignmentCheck,
+
+    /// #MC: machine check
+    MachineCheck,
+
+    /// #XM: SIMD floating-point exception
+    SimdFloatingPoint,
+
+    /// #VE: virtualization exception
+    Virtualization,
+
+    /// #CP: control protection
+    ControlProtection,
+
+    /// Unrecognized trap number
+    Unknown(u64),
+}
+
+impl From<u64> for Trap {
+    fn from(trapno: u64) -> Self {
+        match trapno {
+            0x0 => Self::DivideError,
+            0x1 => Self::Debug,
+            0x2 => Self::Nmi,
+            0x3 => Self::Breakpoint,
+            0x4 => Self::Overflow,
+            0x5 => Self::BoundRange,
+            0x6 => Self::InvalidOpcode,
+            0x7 => Self::DeviceNotAvailable,
+            0x8 => Self::DoubleFault,
+            0x9 => Self::CoprocessorOverrun,
+            0xa => Self::InvalidTss,
+            0xb => Self::SegmentNotPresent,
+            0xc => Self::StackFault,
+            0xd => Self::GeneralProtection,
+            0xe => Self::PageFault,
+            0x10 => Self::FloatingPoint,
+            0x11 => Self::AlignmentCheck,
+            0x12 => Self::MachineCheck,
+            0x13 => Self::SimdFloatingPoint,
+            0x14 => Self::Virtualization,
+            0x15 => Self::ControlProtection,
+            t => Self::Unknown(t),
+        }
+    }
+}
+
+impl core::fmt::Display for Trap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::DivideError => "#DE (divide error)".to_owned(),
+                Self::Debug => "#DB (debug)".to_owned(),
+                Self::Nmi => "NMI (non-maskable interrupt)".to_owned(),
+                Self::Breakpoint => "#BP (breakpoint)".to_owned(),
+                Self::Overflow => "#OF (overflow)".to_owned(),
+                Self::BoundRange => "#BR (bound range exceeded)".to_owned(),
+                Self::InvalidOpcode => "#UD (invalid opcode)".to_owned(),
+                Self::DeviceNotAvailable => {
+                    "#NM (device not available)".to_owned()
+                }
+                Self::DoubleFault => "#DF (double fault)".to_owned(),
+                Self::CoprocessorOverrun => {
+                    "coprocessor segment overrun".to_owned()
+                }
+                Self::InvalidTss => "#TS (invalid TSS)".to_owned(),
+                Self::SegmentNotPresent => {
+                    "#NP (segment not present)".to_owned()
+                }
+                Self::StackFault => "#SS (stack fault)".to_owned(),
+                Self::GeneralProtection => {
+                    "#GP (general protection)".to_owned()
+                }
+                Self::PageFault => "#PF (page fault)".to_owned(),
+                Self::FloatingPoint => "#MF (x87 floating-point)".to_owned(),
+                Self::AlignmentCheck => "#AC (alignment check)".to_owned(),
+                Self::MachineCheck => "#MC (machine check)".to_owned(),
+                Self::SimdFloatingPoint => {
+                    "#XM (SIMD floating-point)".to_owned()
+                }
+                Self::Virtualization => "#VE (virtualization)".to_owned(),
+                Self::ControlProtection => {
+                    "#CP (control protection)".to_owned()
+                }
+                Self::Unknown(t) => format!("<Unknown trap {t:#x}>"),
+            }
+        )
+    }
+}
+
+bitflags::bitflags! {
+    /// The error code of a page fault, as pushed by the processor
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct PageFaultFlags: u64 {
+        /// Fault was due to a protection violation (rather than a
+        /// non-present page)
+        const PRESENT = 0x1;
+
+        /// Fault was due to a write (rather than a read)
+        const WRITE = 0x2;
+
+        /// Fault occurred in user mode
+        const USER = 0x4;
+
+        /// Fault was due to a reserved bit being set in a paging structure
+        const RESERVED = 0x8;
+
+        /// Fault was due to an instruction fetch
+        const INSTRUCTION = 0x10;
+
+        /// Fault was due to a protection key violation
+        const PROTECTION_KEY = 0x20;
+
+        /// Fault was due to a shadow stack access
+        const SHADOW_STACK = 0x40;
+
+        /// Fault was due to an SGX violation
+        const SGX = 0x8000;
+
+        // Bits that we don't know about are nonetheless retained.
+        const _ = !0;
+    }
+}
+
+bitflags::bitflags! {
+    /// The flags register (RFLAGS), as found in the `rfl` register
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct RFlags: u64 {
+        /// Carry flag
+        const CF = 0x1;
+
+        /// Parity flag
+        const PF = 0x4;
+
+        /// Auxiliary carry flag
+        const AF = 0x10;
+
+        /// Zero flag
+        const ZF = 0x40;
+
+        /// Sign flag
+        const SF = 0x80;
+
+        /// Trap flag
+        const TF = 0x100;
+
+        /// Interrupt enable flag
+        const IF = 0x200;
+
+        /// Direction flag
+        const DF = 0x400;
+
+        /// Overflow flag
+        const OF = 0x800;
+
+        /// I/O privilege level (a two-bit field rather than a flag)
+        const IOPL = 0x3000;
+
+        /// Nested task flag
+        const NT = 0x4000;
+
+        /// Resume flag
+        const RF = 0x1_0000;
+
+        /// Virtual-8086 mode flag
+        const VM = 0x2_0000;
+
+        /// Alignment check flag
+        const AC = 0x4_0000;
+
+        /// Virtual interrupt flag
+        const VIF = 0x8_0000;
+
+        /// Virtual interrupt pending flag
+        const VIP = 0x10_0000;
+
+        /// CPUID identification flag
+        const ID = 0x20_0000;
+
+        // Bits that we don't know about are nonetheless retained.
+        const _ = !0;
+    }
+}
+
+impl RFlags {
+    /// Returns the I/O privilege level.
+    pub fn iopl(&self) -> u8 {
+        ((self.bits() & Self::IOPL.bits()) >> 12) as u8
+    }
+}
+
+//
+// Like mdb, we display the flags that are set from most significant to least
+// significant, e.g. `<rf,if,zf,pf>`.
+//
+impl core::fmt::Display for RFlags {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let flags = [
+            (Self::ID, "id"),
+            (Self::VIP, "vip"),
+            (Self::VIF, "vif"),
+            (Self::AC, "ac"),
+            (Self::VM, "vm"),
+            (Self::RF, "rf"),
+            (Self::NT, "nt"),
+            (Self::OF, "of"),
+            (Self::DF, "df"),
+            (Self::IF, "if"),
+            (Self::TF, "tf"),
+            (Self::SF, "sf"),
+            (Self::ZF, "zf"),
+            (Self::AF, "af"),
+            (Self::PF, "pf"),
+            (Self::CF, "cf"),
+        ];
+
+        let set = flags
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>();
+
+        write!(f, "<{}>", set.join(","))
+    }
+}
+
+/// A 64-bit FNV-1a hasher.  This is used when we need a hash that is stable
+/// (for stack signatures), as well as for our maps in the absence of `std`
+/// (and therefore of `RandomState`).
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl core::hash::Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+type MapHasher = std::collections::hash_map::RandomState;
+
+#[cfg(not(feature = "std"))]
+type MapHasher = core::hash::BuildHasherDefault<FnvHasher>;
+
+/// Decoded registers, in the order in which they are displayed by dumpregs()
+/// in the operating system
+pub type Registers = IndexMap<Register, u64, MapHasher>;
+
+/// Panic data flattened into names and values, as returned by
+/// [`PanicData::to_fields`]
+pub type Fields = IndexMap<String, String, MapHasher>;
+
 /// Host panic data, the payload that corresponds to `HSSPanic` as described
-/// in [RFD 316] and implemented in [`kernel_ipcc.h`].
+/// in [RFD 316] and implemented in [`kernel_ipcc.h`].  Fields that describe
+/// how the payload was decoded are added over time, so this structure is
+/// non-exhaustive.
+///
+/// Panic data can be hashed (as for deduplication), but note that every
+/// field -- including the times -- participates in the hash; to group panics
+/// without regard to when they occurred, use [`PanicData::stack_signature`].
 ///
 /// [RFD 316]: https://rfd.shared.oxide.computer/rfd/0316
 /// [`kernel_ipcc.h`]: https://github.com/oxidecomputer/illumos-gate/blob/stlouis/usr/src/uts/oxide/sys/kernel_ipcc.h
 ///
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub struct PanicData {
     /// version of panic data
     pub version: PanicDataVersion,
@@ -227,18 +1134,31 @@ pub struct PanicData {
     /// cause of panic
     pub cause: PanicCause,
 
+    /// true if the cause had to be partially reconstructed due to the
+    /// presence of hubris#1554, in which case it may be approximate
+    pub cause_inferred: bool,
+
+    /// raw value of the cause; if the cause was inferred, this will include
+    /// the reconstructed low byte
+    pub raw_cause: u16,
+
     /// error code associated with trap (if any)
     pub error_code: u32,
 
     /// ID of panicking CPU
     pub cpuid: Cpuid,
 
-    /// panic non-monotonic time (nanoseconds since boot), if present
+    /// panic non-monotonic time (nanoseconds since boot), if present and
+    /// non-zero (a zero time denotes a panic before time was available)
     pub hrtime: Option<MonotonicNanoseconds>,
 
-    /// panic adjusted time (time since epoch), if present
+    /// panic adjusted time (time since epoch), if present and non-zero
     pub time: Option<AdjustedTime>,
 
+    /// true if the adjusted time in the payload was invalid (that is, its
+    /// nanoseconds were out of range), in which case `time` is `None`
+    pub time_invalid: bool,
+
     /// address of panicking thread
     pub thread: Addr,
 
@@ -254,36 +1174,440 @@ pub struct PanicData {
     /// pointer to panic registers
     pub rp: Addr,
 
-    /// panic message, if any
+    /// symbols of `thread`, `pc`, `fp` and `rp`, if they have been resolved
+    /// via [`PanicData::symbolize`]
+    pub addr_symbols: AddrSymbols,
+
+    /// panic message, if any; multiple messages are joined with newlines
     pub message: Option<String>,
 
-    /// trap registers, if present
-    pub registers: Option<IndexMap<Register, u64>>,
+    /// trap registers, if present; these are not present for a panic due to
+    /// an explicit call to panic unless [`PanicDataOptions::call_registers`]
+    /// is set (when compared, the order of registers is not considered)
+    pub registers: Option<Registers>,
+
+    /// registers exactly as they appear in the payload, if present (that is,
+    /// for V2 panic data regardless of the cause)
+    pub raw_registers: Option<RawRegisters>,
 
-    /// panic stack
+    /// panic stack, innermost (that is, most recently called) frame first,
+    /// regardless of the version of the panic data
     pub stack: Vec<StackFrame>,
-}
 
-const IPCC_PANIC_VERSION_MAX: u8 = 0x3f;
+    /// true if the stack index in the payload was out of bounds (and the
+    /// stack was therefore clamped), indicating that the payload is damaged
+    pub stack_clamped: bool,
 
-// Values and structs are defined in usr/src/uts/oxide/sys/kernel_ipcc.h
-// in the `stlouis` branch of `oxidecomputer/illumos-gate`
-const IPCC_PANIC_V1_STACKS: usize = 0x10;
-const IPCC_PANIC_V1_DATALEN: usize = 0x100;
-const IPCC_PANIC_V1_SYMLEN: usize = 0x20;
-const IPCC_PANIC_V1_MSGLEN: usize = 0x80;
+    /// true if the stack was truncated to the maximum number of frames (see
+    /// [`PanicDataOptions::max_stack_frames`])
+    pub stack_truncated: bool,
 
-#[derive(Copy, Clone, Debug, BinRead)]
-#[allow(dead_code)]
-struct IpccPanicDataV1 {
-    ipd_version: u8,
-    ipd_cause: u16,
-    ipd_error: u32,
+    /// ancillary data, in the order in which it appears in the payload
+    pub ancillary: Vec<Vec<u8>>,
 
-    ipd_cpuid: u32,
-    ipd_thread: u64,
-    ipd_addr: u64,
-    ipd_pc: u64,
+    /// true if the payload was shorter than its version requires, in which
+    /// case only those frames and ancillary data that were present have been
+    /// decoded
+    pub truncated: bool,
+
+    /// true if the items in the payload did not total the length indicated
+    /// by its header, suggesting that the items are corrupt
+    pub items_len_mismatch: bool,
+
+    /// true if any `Nop` (padding) items in the payload carried data, which
+    /// is unexpected; the data in such items is ignored
+    pub nop_data: bool,
+
+    /// true if the version of the panic data is not one that we know how to
+    /// decode, in which case only the version, cause and error code (which
+    /// are common to all versions) are valid
+    pub degraded: bool,
+}
+
+/// Panic data along with the bytes from which it was decoded, as returned by
+/// [`PanicData::from_bytes_with_raw`].
+#[derive(Debug)]
+pub struct RawPanicData {
+    /// decoded panic data
+    pub data: PanicData,
+
+    /// bytes that were decoded, after any fixups for hubris#1554
+    pub fixed: Vec<u8>,
+}
+
+/// The architecture of the host from which panic data originates, which
+/// determines the byte order of the panic data and the registers that it
+/// contains.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Architecture {
+    /// AMD64 (x86-64)
+    #[default]
+    Amd64,
+}
+
+impl Architecture {
+    /// Returns the registers of the architecture, in the order in which
+    /// they are displayed.
+    pub fn registers(&self) -> &'static [Register] {
+        match self {
+            Self::Amd64 => Register::all(),
+        }
+    }
+
+    fn endian(&self) -> Endian {
+        match self {
+            Self::Amd64 => Endian::Little,
+        }
+    }
+}
+
+/// Options that control how strictly panic data is decoded; see
+/// [`PanicData::from_bytes_with`].  The defaults are those used by
+/// [`PanicData::from_bytes`]:  decode as much as we can.
+#[derive(Clone, Debug)]
+pub struct PanicDataOptions {
+    lossy_strings: bool,
+    require_determined_version: bool,
+    max_stack_frames: usize,
+    best_effort: bool,
+    call_registers: bool,
+    require_items_len: bool,
+    max_plausible_cpuid: u32,
+    architecture: Architecture,
+    trim_messages: bool,
+}
+
+impl Default for PanicDataOptions {
+    fn default() -> Self {
+        Self {
+            lossy_strings: true,
+            require_determined_version: false,
+            max_stack_frames: usize::MAX,
+            best_effort: false,
+            call_registers: false,
+            require_items_len: false,
+            max_plausible_cpuid: MAX_PLAUSIBLE_CPUID,
+            architecture: Architecture::default(),
+            trim_messages: true,
+        }
+    }
+}
+
+impl PanicDataOptions {
+    /// Returns the default options; this is identical to
+    /// [`PanicDataOptions::default`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// If set (the default), invalid UTF-8 in messages and symbols is
+    /// replaced (or, in the case of V1 symbols, results in no symbol);
+    /// if not set, it results in an error.
+    pub fn lossy_strings(mut self, lossy_strings: bool) -> Self {
+        self.lossy_strings = lossy_strings;
+        self
+    }
+
+    /// If set, panic data whose version had to be inferred (that is, panic
+    /// data that suffers from hubris#1554) results in an error.  Not set by
+    /// default.
+    pub fn require_determined_version(mut self, require: bool) -> Self {
+        self.require_determined_version = require;
+        self
+    }
+
+    /// If set, panic data of a version that we don't know how to decode
+    /// results in degraded [`PanicData`] rather than an error.  Not set by
+    /// default.
+    pub fn best_effort(mut self, best_effort: bool) -> Self {
+        self.best_effort = best_effort;
+        self
+    }
+
+    /// If set, registers are decoded for panics due to an explicit call to
+    /// panic.  Because such panics are not associated with a trap, the
+    /// registers are not trap state, but rather a snapshot taken at the
+    /// panic site; they are therefore not decoded by default.
+    pub fn call_registers(mut self, call_registers: bool) -> Self {
+        self.call_registers = call_registers;
+        self
+    }
+
+    /// If set, V2 panic data whose items do not total the length indicated
+    /// by its header results in an error; if not set (the default), the
+    /// mismatch is merely noted in [`PanicData::items_len_mismatch`].
+    pub fn require_items_len(mut self, require: bool) -> Self {
+        self.require_items_len = require;
+        self
+    }
+
+    /// Sets the largest CPU ID considered plausible when inferring the
+    /// version of panic data that suffers from hubris#1554.  Defaults to
+    /// [`MAX_PLAUSIBLE_CPUID`].
+    pub fn max_plausible_cpuid(mut self, max: u32) -> Self {
+        self.max_plausible_cpuid = max;
+        self
+    }
+
+    /// If set (the default), the NUL padding of the fixed-size message in V1
+    /// panic data is trimmed; if not set, the message is left intact.  (In
+    /// conjunction with [`PanicDataOptions::lossy_strings`] not being set,
+    /// this yields the exact bytes of the message.)
+    pub fn trim_messages(mut self, trim_messages: bool) -> Self {
+        self.trim_messages = trim_messages;
+        self
+    }
+
+    /// Sets the architecture of the host from which the panic data
+    /// originates.  Defaults to [`Architecture::Amd64`].
+    pub fn architecture(mut self, architecture: Architecture) -> Self {
+        self.architecture = architecture;
+        self
+    }
+
+    /// Limits the number of stack frames decoded.  Unlimited by default.
+    pub fn max_stack_frames(mut self, max_stack_frames: usize) -> Self {
+        self.max_stack_frames = max_stack_frames;
+        self
+    }
+
+    fn decode(
+        &self,
+        bytes: &[u8],
+        err: fn(Vec<u8>) -> IpccDataError,
+    ) -> Result<String, IpccDataError> {
+        if self.lossy_strings {
+            Ok(String::from_utf8_lossy(bytes).to_string())
+        } else {
+            core::str::from_utf8(bytes)
+                .map(str::to_string)
+                .map_err(|_| err(bytes.to_vec()))
+        }
+    }
+}
+
+//
+// Values and structs are defined in usr/src/uts/oxide/sys/kernel_ipcc.h
+// in the `stlouis` branch of `oxidecomputer/illumos-gate`; the lengths of
+// the payloads are derived from the structures defined there.
+//
+
+/// The largest CPU ID that is considered plausible.  When inferring the
+/// version of panic data that suffers from hubris#1554, a V2 payload is
+/// unlikely to have a V1 CPU ID that is this small.  This must be at least
+/// the largest CPU ID on any supported system, lest a V1 payload from a
+/// CPU with a larger ID be taken to be V2.
+pub const MAX_PLAUSIBLE_CPUID: u32 = 511;
+
+/// The maximum version of the IPCC panic data (exclusive)
+pub const IPCC_PANIC_VERSION_MAX: u8 = 0x3f;
+
+/// The number of stack frames in V1 panic data
+pub const IPCC_PANIC_V1_STACKS: usize = 0x10;
+
+/// The length of the ancillary data buffer in V1 panic data
+pub const IPCC_PANIC_V1_DATALEN: usize = 0x100;
+
+/// The length of a stack frame symbol in V1 panic data
+pub const IPCC_PANIC_V1_SYMLEN: usize = 0x20;
+
+/// The length of the message in V1 panic data
+pub const IPCC_PANIC_V1_MSGLEN: usize = 0x80;
+
+/// The length of V1 panic data, which is entirely fixed-size
+pub const IPCC_PANIC_V1_LEN: usize = 1
+    + 2
+    + 4
+    + 4
+    + 5 * 8
+    + IPCC_PANIC_V1_MSGLEN
+    + 1
+    + IPCC_PANIC_V1_STACKS * (IPCC_PANIC_V1_SYMLEN + 2 * 8)
+    + 1
+    + IPCC_PANIC_V1_DATALEN;
+
+// The offsets of the stack and ancillary data in V1 panic data; a truncated
+// V1 payload must extend at least to the stack.
+const IPCC_PANIC_V1_STACK_OFFSET: usize =
+    1 + 2 + 4 + 4 + 5 * 8 + IPCC_PANIC_V1_MSGLEN + 1;
+const IPCC_PANIC_V1_STACK_LEN: usize = IPCC_PANIC_V1_SYMLEN + 2 * 8;
+const IPCC_PANIC_V1_DATA_OFFSET: usize = IPCC_PANIC_V1_STACK_OFFSET
+    + IPCC_PANIC_V1_STACKS * IPCC_PANIC_V1_STACK_LEN
+    + 1;
+
+/// The length of the fixed-size portion of V2 panic data that precedes its
+/// variable-length items
+pub const IPCC_PANIC_V2_HEADER_LEN: usize =
+    1 + 2 + 4 + 8 + 2 * 8 + 4 + 5 * 8 + 30 * 8 + 2 + 2;
+
+// The number of innermost stack frames among which the program counter of a
+// trap must be found for the stack to be consistent with it (see
+// PanicData::validate_stack_consistency()).
+const TRAP_PC_FRAMES: usize = 4;
+
+// The number of characters of the panic message that we include in a summary
+// (see PanicData::summary()) before truncating it.
+const SUMMARY_MSGLEN: usize = 60;
+
+/// Returns true if the given payload is empty or consists entirely of zeros,
+/// which denotes that no panic was recorded; it is for such a payload that
+/// [`PanicData::from_bytes`] returns `None`.
+pub fn is_empty_payload(d: &[u8]) -> bool {
+    d.iter().all(|&b| b == 0)
+}
+
+/// Returns true if the given panic payload suffers from [hubris#1554] -- that
+/// is, if it is not empty (see [`is_empty_payload`]) and its first byte is
+/// not a valid version, such that its missing bytes must be reconstructed
+/// in order to decode it.  This denotes an SP that lacks the fix.  (For
+/// decoded panic data, the same is indicated by an inferred version; see
+/// [`PanicDataVersion::is_inferred`].)
+///
+/// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+///
+pub fn payload_needs_1554_fixup(d: &[u8]) -> bool {
+    match d.first() {
+        Some(&b) => !is_panic_version(b) && !is_empty_payload(d),
+        None => false,
+    }
+}
+
+fn is_panic_version(b: u8) -> bool {
+    b < IPCC_PANIC_VERSION_MAX && b != 0
+}
+
+/// Returns a hex dump of the given panic payload, annotated with the offsets
+/// and names of the fields of the given version of the panic data (the
+/// version byte of the payload itself is not consulted, allowing a payload
+/// to be examined as any version).  Fields that extend beyond the end of the
+/// payload are marked as truncated, and any bytes beyond the last field are
+/// marked as trailing.  This is intended for debugging payloads that fail to
+/// decode (or that decode unexpectedly); the payload is not validated.
+pub fn annotate_panic_bytes(d: &[u8], version: u8) -> String {
+    let mut fields: Vec<(String, usize)> = vec![];
+
+    let common = [("version", 1), ("cause", 2), ("error", 4)];
+    let addrs = [("thread", 8), ("addr", 8), ("pc", 8), ("fp", 8), ("rp", 8)];
+
+    match version {
+        1 => {
+            fields.extend(common.iter().map(|&(f, l)| (f.to_string(), l)));
+            fields.push(("cpuid".to_string(), 4));
+            fields.extend(addrs.iter().map(|&(f, l)| (f.to_string(), l)));
+            fields.push(("message".to_string(), IPCC_PANIC_V1_MSGLEN));
+            fields.push(("stackidx".to_string(), 1));
+
+            for i in 0..IPCC_PANIC_V1_STACKS {
+                fields.push((format!("stack[{i}]"), IPCC_PANIC_V1_STACK_LEN));
+            }
+
+            fields.push(("dataidx".to_string(), 1));
+            fields.push(("data".to_string(), IPCC_PANIC_V1_DATALEN));
+        }
+
+        2 => {
+            fields.extend(common.iter().map(|&(f, l)| (f.to_string(), l)));
+            fields.push(("hrtime".to_string(), 8));
+            fields.push(("hrestime".to_string(), 16));
+            fields.push(("cpuid".to_string(), 4));
+            fields.extend(addrs.iter().map(|&(f, l)| (f.to_string(), l)));
+            fields.push(("registers".to_string(), 30 * 8));
+            fields.push(("nitems".to_string(), 2));
+            fields.push(("items_len".to_string(), 2));
+
+            //
+            // The items are variable in length, so we walk them as the
+            // decoder would, stopping at the first item that is truncated
+            // or whose length is invalid.
+            //
+            let nitems = match d.get(IPCC_PANIC_V2_HEADER_LEN - 4..) {
+                Some(&[lo, hi, ..]) => u16::from_le_bytes([lo, hi]),
+                _ => 0,
+            };
+
+            let mut offset = IPCC_PANIC_V2_HEADER_LEN;
+
+            for i in 0..nitems {
+                let (ftype, len) = match d.get(offset..offset + 3) {
+                    Some(&[ftype, lo, hi]) => {
+                        (ftype, usize::from(u16::from_le_bytes([lo, hi])))
+                    }
+                    _ => break,
+                };
+
+                if len < 3 {
+                    break;
+                }
+
+                let ftype = IpccPanicItemType::from(ftype);
+                fields.push((format!("item[{i}] ({ftype:?})"), len));
+                offset += len;
+            }
+        }
+
+        _ => {}
+    }
+
+    let mut rval = String::new();
+    let mut offset = 0;
+
+    let dump = |rval: &mut String, offset: usize, len: usize, label: &str| {
+        let end = core::cmp::min(offset + len, d.len());
+        let trunc = if end < offset + len {
+            " (truncated)"
+        } else {
+            ""
+        };
+
+        rval.push_str(&format!("{offset:#06x}  {label}{trunc}\n"));
+
+        for chunk in d[offset..end].chunks(16) {
+            let bytes = chunk
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            rval.push_str(&format!("        {bytes}\n"));
+        }
+    };
+
+    for (label, len) in &fields {
+        if offset >= d.len() {
+            break;
+        }
+
+        dump(&mut rval, offset, *len, label.as_str());
+        offset += len;
+    }
+
+    if offset < d.len() {
+        dump(&mut rval, offset, d.len() - offset, "trailing");
+    }
+
+    rval
+}
+
+fn check_panic_len(d: &[u8], need: usize) -> Result<(), IpccDataError> {
+    if d.len() < need {
+        return Err(IpccDataError::PayloadTooShort { got: d.len(), need });
+    }
+
+    Ok(())
+}
+
+#[derive(Copy, Clone, Debug, BinRead)]
+#[allow(dead_code)]
+struct IpccPanicDataV1 {
+    ipd_version: u8,
+    ipd_cause: u16,
+    ipd_error: u32,
+
+    ipd_cpuid: u32,
+    ipd_thread: u64,
+    ipd_addr: u64,
+    ipd_pc: u64,
     ipd_fp: u64,
     ipd_rp: u64,
 
@@ -303,67 +1627,167 @@ struct IpccPanicStackV1 {
     ips_offset: u64,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, BinRead)]
-struct IpccPanicRegs {
-    savfp: u64,
-    savpc: u64,
-    rdi: u64,
-    rsi: u64,
-    rdx: u64,
-    rcx: u64,
-    r8: u64,
-    r9: u64,
-    rax: u64,
-    rbx: u64,
-    rbp: u64,
-    r10: u64,
-    r11: u64,
-    r12: u64,
-    r13: u64,
-    r14: u64,
-    r15: u64,
-    fsbase: u64,
-    gsbase: u64,
-    ds: u64,
-    es: u64,
-    fs: u64,
-    gs: u64,
-    trapno: u64,
-    err: u64,
-    rip: u64,
-    cs: u64,
-    rfl: u64,
-    rsp: u64,
-    ss: u64,
+/// The registers of a panic exactly as they appear in the payload,
+/// including the saved frame pointer and program counter (`savfp` and
+/// `savpc`) that are not otherwise decoded (see [`PanicData::registers`]).
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, Hash, BinRead, BinWrite,
+)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawRegisters {
+    pub savfp: u64,
+    pub savpc: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub rax: u64,
+    pub rbx: u64,
+    pub rbp: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+    pub fsbase: u64,
+    pub gsbase: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+    pub trapno: u64,
+    pub err: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub rfl: u64,
+    pub rsp: u64,
+    pub ss: u64,
 }
 
-#[derive(Debug, BinRead)]
+impl RawRegisters {
+    fn get(&self, reg: Register) -> u64 {
+        match reg {
+            Register::rdi => self.rdi,
+            Register::rsi => self.rsi,
+            Register::rdx => self.rdx,
+            Register::rcx => self.rcx,
+            Register::r8 => self.r8,
+            Register::r9 => self.r9,
+            Register::rax => self.rax,
+            Register::rbx => self.rbx,
+            Register::rbp => self.rbp,
+            Register::r10 => self.r10,
+            Register::r11 => self.r11,
+            Register::r12 => self.r12,
+            Register::r13 => self.r13,
+            Register::r14 => self.r14,
+            Register::r15 => self.r15,
+            Register::fsbase => self.fsbase,
+            Register::gsbase => self.gsbase,
+            Register::ds => self.ds,
+            Register::es => self.es,
+            Register::fs => self.fs,
+            Register::gs => self.gs,
+            Register::trapno => self.trapno,
+            Register::err => self.err,
+            Register::rip => self.rip,
+            Register::cs => self.cs,
+            Register::rfl => self.rfl,
+            Register::rsp => self.rsp,
+            Register::ss => self.ss,
+        }
+    }
+
+    fn set(&mut self, reg: Register, val: u64) {
+        *match reg {
+            Register::rdi => &mut self.rdi,
+            Register::rsi => &mut self.rsi,
+            Register::rdx => &mut self.rdx,
+            Register::rcx => &mut self.rcx,
+            Register::r8 => &mut self.r8,
+            Register::r9 => &mut self.r9,
+            Register::rax => &mut self.rax,
+            Register::rbx => &mut self.rbx,
+            Register::rbp => &mut self.rbp,
+            Register::r10 => &mut self.r10,
+            Register::r11 => &mut self.r11,
+            Register::r12 => &mut self.r12,
+            Register::r13 => &mut self.r13,
+            Register::r14 => &mut self.r14,
+            Register::r15 => &mut self.r15,
+            Register::fsbase => &mut self.fsbase,
+            Register::gsbase => &mut self.gsbase,
+            Register::ds => &mut self.ds,
+            Register::es => &mut self.es,
+            Register::fs => &mut self.fs,
+            Register::gs => &mut self.gs,
+            Register::trapno => &mut self.trapno,
+            Register::err => &mut self.err,
+            Register::rip => &mut self.rip,
+            Register::cs => &mut self.cs,
+            Register::rfl => &mut self.rfl,
+            Register::rsp => &mut self.rsp,
+            Register::ss => &mut self.ss,
+        } = val;
+    }
+}
+
+#[derive(Debug, BinRead, BinWrite)]
 #[allow(dead_code)]
 struct IpccHresTime {
     tv_sec: u64,
     tv_nsec: u64,
 }
 
-#[derive(Debug, PartialEq, BinRead)]
-#[br(repr = u8)]
+#[derive(Debug, PartialEq)]
+#[allow(dead_code)]
 enum IpccPanicItemType {
     Nop,
     Message,
     StackEntry,
     Ancillary,
+    Unknown(u8),
 }
 
-#[derive(Debug, BinRead)]
+impl From<u8> for IpccPanicItemType {
+    fn from(ftype: u8) -> Self {
+        match ftype {
+            0 => Self::Nop,
+            1 => Self::Message,
+            2 => Self::StackEntry,
+            3 => Self::Ancillary,
+            t => Self::Unknown(t),
+        }
+    }
+}
+
+impl From<&IpccPanicItemType> for u8 {
+    fn from(ftype: &IpccPanicItemType) -> Self {
+        match ftype {
+            IpccPanicItemType::Nop => 0,
+            IpccPanicItemType::Message => 1,
+            IpccPanicItemType::StackEntry => 2,
+            IpccPanicItemType::Ancillary => 3,
+            IpccPanicItemType::Unknown(t) => *t,
+        }
+    }
+}
+
+#[derive(Debug, BinRead, BinWrite)]
 #[allow(dead_code)]
 struct IpccPanicItem {
+    #[br(map = |t: u8| IpccPanicItemType::from(t))]
+    #[bw(map = u8::from)]
     ftype: IpccPanicItemType,
     len: u16,
     #[br(count = len.saturating_sub(3))]
     data: Vec<u8>,
 }
 
-#[derive(Debug, BinRead)]
+#[derive(Debug, BinRead, BinWrite)]
 #[allow(dead_code)]
 struct IpccPanicStack {
     addr: u64,
@@ -372,7 +1796,17 @@ struct IpccPanicStack {
     symbol: Vec<u8>,
 }
 
+//
+// The header that is common to all versions of the panic data.
+//
 #[derive(Debug, BinRead)]
+struct IpccPanicHeader {
+    version: u8,
+    cause: u16,
+    error: u32,
+}
+
+#[derive(Debug, BinRead, BinWrite)]
 #[allow(dead_code)]
 struct IpccPanicDataV2 {
     #[br(assert(version == 2))]
@@ -390,7 +1824,7 @@ struct IpccPanicDataV2 {
     fp: u64,
     rp: u64,
 
-    registers: IpccPanicRegs,
+    registers: RawRegisters,
 
     nitems: u16,
     items_len: u16,
@@ -412,155 +1846,426 @@ struct IpccPanicDataV2 {
 // enough, especially when coupled with the panic message), but we also need
 // to infer the version of the structure.
 //
-fn fix_panic_data(d: Vec<u8>) -> Result<(PanicDataVersion, Vec<u8>)> {
+fn fix_panic_data<'a>(
+    d: &'a [u8],
+    opts: &PanicDataOptions,
+) -> Result<(PanicDataVersion, Cow<'a, [u8]>), IpccDataError> {
     //
     // In some cases, `ipd_cause` is unambiguous based on the first byte;
     // otherwise, we populate a generic value.
     //
     let missing_ipd_cause_byte = match d[0] {
-        b if b < IPCC_PANIC_VERSION_MAX && b != 0 => {
-            return Ok((PanicDataVersion::Determined(b), d));
+        b if is_panic_version(b) => {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(version = b, "panic data version determined");
+
+            return Ok((PanicDataVersion::Determined(b), Cow::Borrowed(d)));
         }
         0xca => 0x11,
         0x5e => 0x00,
         0xa9 => 0x00, // fault number is unknown
         0xeb => 0xff, // can't distinguish between different 0xeb**
         b => {
-            bail!("could not decode `ipd_cause`: {b:#04x}");
+            #[cfg(feature = "tracing")]
+            tracing::debug!(first_byte = b, "unrecognized ipd_cause byte");
+
+            return Err(IpccDataError::UnrecognizedCause(b));
         }
     };
 
-    //
-    // Now we need to determine what version we're looking at. As of this writing,
-    // there are two versions of the IPCC panic data -- and they differ enough
-    // in their binary payloads that we can reasonably infer that if the symbol
-    // for every stack frame is a valid UTF-8 string, it must be a V1 payload.
-    // (As an added check, we also check that the CPU ID -- a 32-bit value --
-    // is in the realm of a practically valid CPU ID.)
-    //
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        first_byte = d[0],
+        cause_byte = missing_ipd_cause_byte,
+        "reconstructing ipd_cause for hubris#1554",
+    );
+
     let mut fixed = vec![0xff, missing_ipd_cause_byte];
-    fixed.extend(d);
+    fixed.extend_from_slice(d);
+
+    let version = infer_panic_version(&fixed, opts)?;
+    fixed[0] = version.number();
+
+    Ok((version, Cow::Owned(fixed)))
+}
 
-    let mut cursor: Cursor<&Vec<u8>> = Cursor::new(fixed.as_ref());
-    let check = IpccPanicDataV1::read_le(&mut cursor)
-        .context("failed to deserialize panic data as ")?;
+//
+// When the version is missing, we need to infer it.  As of this writing,
+// there are two versions of the IPCC panic data -- and they differ enough
+// in their binary payloads that we can reasonably infer that if the symbol
+// for every stack frame is a valid UTF-8 string, it must be a V1 payload.
+// (As an added check, we also check that the CPU ID -- a 32-bit value --
+// is in the realm of a practically valid CPU ID.)  The payload is expected
+// to have had its missing bytes restored, though the version byte itself is
+// ignored.  A payload that is too short to hold a V1 structure may yet be a
+// truncated V1 payload, so we read it as V1 padded with zeros -- but check
+// only those bytes of each symbol that are actually present.
+//
+pub(crate) fn infer_panic_version(
+    fixed: &[u8],
+    opts: &PanicDataOptions,
+) -> Result<PanicDataVersion, IpccDataError> {
+    let endian = opts.architecture.endian();
+    let max_plausible_cpuid = opts.max_plausible_cpuid;
+    let len = fixed.len();
+
+    let padded = match len < IPCC_PANIC_V1_LEN {
+        true => {
+            let mut padded = fixed.to_vec();
+            padded.resize(IPCC_PANIC_V1_LEN, 0);
+            Cow::Owned(padded)
+        }
+        false => Cow::Borrowed(fixed),
+    };
+
+    let mut cursor = Cursor::new(&padded[..]);
+    let check = IpccPanicDataV1::read_options(&mut cursor, endian, ())
+        .map_err(IpccDataError::InferVersion)?;
 
     let cpuid = check.ipd_cpuid;
+    let symbols_valid = check.ipd_stack.iter().enumerate().all(|(ndx, s)| {
+        let offset = IPCC_PANIC_V1_STACK_OFFSET + ndx * IPCC_PANIC_V1_STACK_LEN;
+        let present = len.saturating_sub(offset).min(IPCC_PANIC_V1_SYMLEN);
 
-    let version = if cpuid < 512
-        && !check
-            .ipd_stack
-            .iter()
-            .any(|s| std::str::from_utf8(&s.ips_symbol).is_err())
-    {
+        //
+        // A symbol that has been cut short may end mid-character, which we
+        // don't hold against it.
+        //
+        match core::str::from_utf8(&s.ips_symbol[..present]) {
+            Ok(_) => true,
+            Err(e) => present < IPCC_PANIC_V1_SYMLEN && e.error_len().is_none(),
+        }
+    });
+
+    let version = if cpuid <= max_plausible_cpuid && symbols_valid {
         PanicDataVersion::Inferred(1)
     } else {
         PanicDataVersion::Inferred(2)
     };
 
-    fixed[0] = version.number();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        cpuid,
+        max_plausible_cpuid,
+        symbols_valid,
+        version = version.number(),
+        "inferred panic data version",
+    );
 
-    Ok((version, fixed))
+    Ok(version)
 }
 
-impl PanicData {
-    fn from_v1(version: PanicDataVersion, d: Vec<u8>) -> Result<Self> {
-        let mut cursor = Cursor::new(d);
-        let p = IpccPanicDataV1::read_le(&mut cursor)
-            .context("failed to deserialize panic data")?;
+// Panic data along with the bytes from which it was decoded and the number of
+// bytes of the payload that it consumed
+type DecodedPanicData<'a> = (PanicData, Cow<'a, [u8]>, usize);
+
+//
+// A decoder for a particular version of the panic data.  Adding a new
+// version consists of implementing this trait for it and adding it to
+// `PANIC_DECODERS`.
+//
+trait PanicPayload {
+    const VERSION: u8;
+
+    //
+    // Decodes the payload, returning the panic data along with the number of
+    // bytes of the payload that were consumed.
+    //
+    fn decode(
+        version: PanicDataVersion,
+        d: &[u8],
+        opts: &PanicDataOptions,
+    ) -> Result<(PanicData, usize), IpccDataError>;
+}
+
+type PanicDecoder = fn(
+    PanicDataVersion,
+    &[u8],
+    &PanicDataOptions,
+) -> Result<(PanicData, usize), IpccDataError>;
+
+const PANIC_DECODERS: &[(u8, PanicDecoder)] = &[
+    (IpccPanicDataV1::VERSION, IpccPanicDataV1::decode),
+    (IpccPanicDataV2::VERSION, IpccPanicDataV2::decode),
+];
+
+fn panic_decoder(version: u8) -> Option<PanicDecoder> {
+    PANIC_DECODERS
+        .iter()
+        .find(|(v, _)| *v == version)
+        .map(|(_, decoder)| *decoder)
+}
 
-        let message = match std::str::from_utf8(&p.ipd_message) {
-            Ok(s) => s.trim_matches('\0').to_string(),
-            Err(_) => {
-                bail!("failed to decode ipd_message: {:#x?}", p.ipd_message);
+impl PanicPayload for IpccPanicDataV1 {
+    const VERSION: u8 = 1;
+
+    fn decode(
+        version: PanicDataVersion,
+        d: &[u8],
+        opts: &PanicDataOptions,
+    ) -> Result<(PanicData, usize), IpccDataError> {
+        check_panic_len(d, IPCC_PANIC_V1_STACK_OFFSET)?;
+
+        //
+        // A V1 payload that has been truncated (as when captured partially)
+        // still has its most important fields at the front; if we have at
+        // least the fields that precede the stack, we decode as much as is
+        // present, ignoring any frames or ancillary data that have been cut.
+        //
+        let len = d.len();
+        let truncated = len < IPCC_PANIC_V1_LEN;
+        let consumed = len.min(IPCC_PANIC_V1_LEN);
+
+        let padded = match truncated {
+            true => {
+                let mut padded = d.to_vec();
+                padded.resize(IPCC_PANIC_V1_LEN, 0);
+                Cow::Owned(padded)
             }
+            false => Cow::Borrowed(d),
+        };
+
+        let mut cursor = Cursor::new(&padded[..]);
+        let endian = opts.architecture.endian();
+        let p = IpccPanicDataV1::read_options(&mut cursor, endian, ())
+            .map_err(IpccDataError::Deserialize)?;
+
+        let frames =
+            (len - IPCC_PANIC_V1_STACK_OFFSET) / IPCC_PANIC_V1_STACK_LEN;
+        let data = len.saturating_sub(IPCC_PANIC_V1_DATA_OFFSET);
+
+        //
+        // As with V2, we (by default) decode the message lossily:  a stray
+        // byte in the message shouldn't render the entire payload
+        // undecodable.
+        //
+        let message =
+            opts.decode(&p.ipd_message, IpccDataError::InvalidMessage)?;
+
+        let message = match opts.trim_messages {
+            true => message.trim_matches('\0').to_string(),
+            false => message,
         };
 
+        //
+        // A stack index beyond the end of the stack indicates corruption; we
+        // clamp it, and record that we did so.
+        //
+        let stack_clamped = usize::from(p.ipd_stackidx) > IPCC_PANIC_V1_STACKS;
+        let stackidx = usize::from(p.ipd_stackidx)
+            .min(IPCC_PANIC_V1_STACKS)
+            .min(frames);
+
+        //
+        // The kernel fills `ipd_stack` from the innermost frame outward (as
+        // getpcstack() does), which is the order that we preserve in `stack`.
+        //
         let mut stack = vec![];
 
-        for (ndx, s) in p.ipd_stack.iter().enumerate() {
-            if ndx >= p.ipd_stackidx.into() {
-                break;
-            }
+        for s in &p.ipd_stack[..stackidx] {
+            let symbol = match core::str::from_utf8(&s.ips_symbol) {
+                Ok(s) => Some(s.trim_matches('\0').to_string()),
+                Err(_) if opts.lossy_strings => None,
+                Err(_) => {
+                    return Err(IpccDataError::InvalidSymbol(
+                        s.ips_symbol.to_vec(),
+                    ));
+                }
+            };
 
             stack.push(StackFrame {
                 address: Addr(s.ips_addr),
                 offset: s.ips_offset,
-                symbol: match std::str::from_utf8(&s.ips_symbol) {
-                    Ok(s) => Some(s.trim_matches('\0').to_string()),
-                    Err(_) => None,
-                },
+                symbol,
             });
         }
 
-        Ok(Self {
+        let stack_truncated = stack.len() > opts.max_stack_frames;
+        stack.truncate(opts.max_stack_frames);
+
+        //
+        // The V1 ancillary data is a single buffer, of which `ipd_dataidx`
+        // bytes are valid; we clamp this to the size of the buffer lest a
+        // corrupt index take us somewhere we don't want to go.
+        //
+        let dataidx = usize::from(p.ipd_dataidx)
+            .min(IPCC_PANIC_V1_DATALEN)
+            .min(data);
+
+        let ancillary = match dataidx {
+            0 => vec![],
+            n => vec![p.ipd_data[..n].to_vec()],
+        };
+
+        let cause_inferred = version.is_inferred();
+
+        let data = PanicData {
             version,
             cause: p.ipd_cause.into(),
+            cause_inferred,
+            raw_cause: p.ipd_cause,
             error_code: p.ipd_error,
             cpuid: Cpuid(p.ipd_cpuid),
             hrtime: None,
             time: None,
+            time_invalid: false,
             thread: Addr(p.ipd_thread),
             addr: Addr(p.ipd_addr),
             pc: Addr(p.ipd_pc),
             fp: Addr(p.ipd_fp),
             rp: Addr(p.ipd_rp),
+            addr_symbols: AddrSymbols::default(),
             message: Some(message),
             registers: None,
+            raw_registers: None,
             stack,
-        })
+            stack_clamped,
+            stack_truncated,
+            ancillary,
+            truncated,
+            items_len_mismatch: false,
+            nop_data: false,
+            degraded: false,
+        };
+
+        Ok((data, consumed))
     }
+}
+
+impl PanicPayload for IpccPanicDataV2 {
+    const VERSION: u8 = 2;
+
+    fn decode(
+        version: PanicDataVersion,
+        d: &[u8],
+        opts: &PanicDataOptions,
+    ) -> Result<(PanicData, usize), IpccDataError> {
+        check_panic_len(d, IPCC_PANIC_V2_HEADER_LEN)?;
+
+        //
+        // Before we decode items based on the item count (which we don't
+        // trust), we check that the payload could plausibly contain that
+        // many items:  each consists of at least its three byte header.
+        //
+        let endian = opts.architecture.endian();
+        let n = IPCC_PANIC_V2_HEADER_LEN - 4;
+        let nitems = match endian {
+            Endian::Little => u16::from_le_bytes([d[n], d[n + 1]]),
+            Endian::Big => u16::from_be_bytes([d[n], d[n + 1]]),
+        };
+        let len = d.len() - IPCC_PANIC_V2_HEADER_LEN;
+
+        if usize::from(nitems) * 3 > len {
+            return Err(IpccDataError::ImplausibleItemCount { nitems, len });
+        }
 
-    fn from_v2(version: PanicDataVersion, d: Vec<u8>) -> Result<Self> {
         let mut cursor = Cursor::new(d);
-        let p = IpccPanicDataV2::read_le(&mut cursor)
-            .context("failed to deserialize panic data")?;
+        let p = IpccPanicDataV2::read_options(&mut cursor, endian, ())
+            .map_err(IpccDataError::Deserialize)?;
+        let consumed = cursor.position() as usize;
+
+        //
+        // An item's length includes its three byte header; anything shorter
+        // denotes corrupt framing rather than an empty item.
+        //
+        if let Some((index, i)) =
+            p.items.iter().enumerate().find(|(_, i)| i.len < 3)
+        {
+            return Err(IpccDataError::InvalidItemLength { index, len: i.len });
+        }
+
+        //
+        // The header also tells us the total length of the items, which we
+        // use as a check on their integrity.
+        //
+        let got: usize = p.items.iter().map(|i| usize::from(i.len)).sum();
+        let items_len_mismatch = got != usize::from(p.items_len);
 
-        let messages = p
+        if items_len_mismatch && opts.require_items_len {
+            return Err(IpccDataError::ItemsLengthMismatch {
+                expected: p.items_len,
+                got,
+            });
+        }
+
+        //
+        // `Nop` items are padding:  they are skipped, and should never carry
+        // data.  If one does, we note it (but otherwise ignore its data).
+        //
+        let items = p
             .items
             .iter()
-            .filter(|i| i.ftype == IpccPanicItemType::Message)
+            .filter(|i| i.ftype != IpccPanicItemType::Nop)
             .collect::<Vec<_>>();
 
+        let nop_data = p
+            .items
+            .iter()
+            .any(|i| i.ftype == IpccPanicItemType::Nop && !i.data.is_empty());
+
+        //
+        // The kernel may emit more than one message item (e.g., a panic
+        // message followed by a secondary diagnostic message); if it does, we
+        // join them together with newlines, in the order of the items.
+        //
+        let messages = items
+            .iter()
+            .filter(|i| i.ftype == IpccPanicItemType::Message)
+            .map(|i| opts.decode(&i.data, IpccDataError::InvalidMessage))
+            .collect::<Result<Vec<_>, _>>()?;
+
         let message = match messages.len() {
             0 => None,
-            1 => Some(String::from_utf8_lossy(&messages[0].data).to_string()),
-            _ => {
-                bail!("found unexpected message items in panic data");
-            }
+            _ => Some(messages.join("\n")),
         };
 
+        //
+        // As with V1, stack entries appear innermost frame first; we keep
+        // them in item order so that the stacks of both versions agree.
+        //
         let mut stack = vec![];
 
-        for i in p
-            .items
+        for i in items
             .iter()
             .filter(|i| i.ftype == IpccPanicItemType::StackEntry)
         {
             let mut cursor = Cursor::new(&i.data);
-            let ps = IpccPanicStack::read_le(&mut cursor)
-                .context(format!("failed to deserialize item {i:#x?}"))?;
+            let ps = IpccPanicStack::read_options(&mut cursor, endian, ())
+                .map_err(|err| IpccDataError::DeserializeItem {
+                    item: format!("{i:#x?}"),
+                    err,
+                })?;
 
             stack.push(StackFrame {
                 address: Addr(ps.addr),
                 offset: ps.offset,
                 symbol: match ps.symbol.len() {
                     0 => None,
-                    _ => Some(String::from_utf8_lossy(&ps.symbol).to_string()),
+                    _ => Some(
+                        opts.decode(&ps.symbol, IpccDataError::InvalidSymbol)?,
+                    ),
                 },
             });
         }
 
-        let cause: PanicCause = p.cause.into();
+        let stack_truncated = stack.len() > opts.max_stack_frames;
+        stack.truncate(opts.max_stack_frames);
+
+        //
+        // Ancillary items are opaque to us; we hand them back in the order
+        // in which they appear.  Any items of a type that we don't recognize
+        // are skipped.
+        //
+        let ancillary = items
+            .iter()
+            .filter(|i| i.ftype == IpccPanicItemType::Ancillary)
+            .map(|i| i.data.clone())
+            .collect::<Vec<_>>();
 
-        let registers = if cause != PanicCause::Call {
-            let mut registers = IndexMap::new();
+        let cause: PanicCause = p.cause.into();
 
-            macro_rules! register {
-                ($reg:ident) => {
-                    registers.insert(Register::$reg, p.registers.$reg);
-                };
-            }
+        let registers = if cause != PanicCause::Call || opts.call_registers {
+            let mut registers = Registers::default();
 
             //
             // We set the registers in the same order in which they are displayed in
@@ -568,67 +2273,124 @@ impl PanicData {
             // wants to display them to be able to iterate over them and get
             // sufficiently familiar output.
             //
-            register!(rdi);
-            register!(rsi);
-            register!(rdx);
-            register!(rcx);
-            register!(r8);
-            register!(r9);
-            register!(rax);
-            register!(rbx);
-            register!(rbp);
-            register!(r10);
-            register!(r11);
-            register!(r12);
-            register!(r13);
-            register!(r14);
-            register!(fsbase);
-            register!(gsbase);
-            register!(es);
-            register!(fs);
-            register!(gs);
-            register!(trapno);
-            register!(err);
-            register!(rip);
-            register!(cs);
-            register!(rfl);
-            register!(rsp);
-            register!(ss);
+            registers.extend(
+                opts.architecture
+                    .registers()
+                    .iter()
+                    .map(|&r| (r, p.registers.get(r))),
+            );
 
             Some(registers)
         } else {
             None
         };
 
-        let nsec: u32 = match p.hrestime.tv_nsec.try_into() {
-            Ok(nsec) => nsec,
-            Err(_) => {
-                bail!("illegal nsec value {:?}", p.hrestime);
-            }
+        //
+        // A panic very early in boot can precede the availability of time,
+        // in which case the times are zero; we report these as absent (as
+        // for V1) rather than as times at the epoch.  A corrupt adjusted time
+        // shouldn't prevent us from decoding the rest of the panic:  if the
+        // nanoseconds are out of range, we discard the time and note that we
+        // have done so.
+        //
+        let hrtime = match p.hrtime {
+            0 => None,
+            t => Some(MonotonicNanoseconds(t)),
         };
 
-        Ok(Self {
+        let (time, time_invalid) = match u32::try_from(p.hrestime.tv_nsec) {
+            Ok(0) if p.hrestime.tv_sec == 0 => (None, false),
+            Ok(nsec) if nsec < 1_000_000_000 => (
+                Some(AdjustedTime {
+                    sec: p.hrestime.tv_sec,
+                    nsec,
+                }),
+                false,
+            ),
+            _ => (None, true),
+        };
+
+        let cause_inferred = version.is_inferred();
+
+        let data = PanicData {
             version,
             cause,
+            cause_inferred,
+            raw_cause: p.cause,
             error_code: p.error,
             cpuid: Cpuid(p.cpuid),
-            hrtime: Some(MonotonicNanoseconds(p.hrtime)),
-            time: Some(AdjustedTime {
-                sec: p.hrestime.tv_sec,
-                nsec,
-            }),
+            hrtime,
+            time,
+            time_invalid,
             thread: Addr(p.thread),
             addr: Addr(p.addr),
             pc: Addr(p.pc),
             fp: Addr(p.fp),
             rp: Addr(p.rp),
+            addr_symbols: AddrSymbols::default(),
             message,
             registers,
+            raw_registers: Some(p.registers),
             stack,
-        })
+            stack_clamped: false,
+            stack_truncated,
+            ancillary,
+            truncated: false,
+            items_len_mismatch,
+            nop_data,
+            degraded: false,
+        };
+
+        Ok((data, consumed))
     }
+}
 
-    /// Infer an IPCC data payload to be `HSSPanic`, creating [`PanicData`]
+impl PanicData {
+    fn from_header(
+        version: PanicDataVersion,
+        d: &[u8],
+        opts: &PanicDataOptions,
+    ) -> Result<Self, IpccDataError> {
+        let endian = opts.architecture.endian();
+        let mut cursor = Cursor::new(d);
+        let p = IpccPanicHeader::read_options(&mut cursor, endian, ())
+            .map_err(IpccDataError::Deserialize)?;
+
+        debug_assert_eq!(p.version, version.number());
+
+        let cause_inferred = version.is_inferred();
+
+        Ok(Self {
+            version,
+            cause: p.cause.into(),
+            cause_inferred,
+            raw_cause: p.cause,
+            error_code: p.error,
+            cpuid: Cpuid(0),
+            hrtime: None,
+            time: None,
+            time_invalid: false,
+            thread: Addr(0),
+            addr: Addr(0),
+            pc: Addr(0),
+            fp: Addr(0),
+            rp: Addr(0),
+            addr_symbols: AddrSymbols::default(),
+            message: None,
+            registers: None,
+            raw_registers: None,
+            stack: vec![],
+            stack_clamped: false,
+            stack_truncated: false,
+            ancillary: vec![],
+            truncated: false,
+            items_len_mismatch: false,
+            nop_data: false,
+            degraded: true,
+        })
+    }
+
+    /// Infer an IPCC data payload to be `HSSPanic`, creating [`PanicData`]
     /// from received bytes.  This implementation will work around
     /// any presence of [hubris#1554]; the returned [`PanicDataVersion`] will
     /// indicate if the version was able to be determined or had to
@@ -636,54 +2398,4124 @@ impl PanicData {
     ///
     /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
     ///
-    pub fn from_bytes(d: Vec<u8>) -> Result<Option<Self>> {
-        if !d.iter().any(|&s| s != 0) {
-            Ok(None)
-        } else {
-            let (version, data) = fix_panic_data(d)?;
+    pub fn from_bytes(d: Vec<u8>) -> Result<Option<Self>, IpccDataError> {
+        Self::from_slice(&d)
+    }
+
+    /// Like [`PanicData::from_bytes`], but operating on borrowed bytes.  The
+    /// payload is only copied if it needs to be fixed up due to
+    /// [hubris#1554].
+    ///
+    /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+    ///
+    pub fn from_slice(d: &[u8]) -> Result<Option<Self>, IpccDataError> {
+        Self::from_slice_with(d, &PanicDataOptions::default())
+    }
+
+    /// Like [`PanicData::from_bytes`], but with the specified options.
+    pub fn from_bytes_with(
+        d: Vec<u8>,
+        opts: &PanicDataOptions,
+    ) -> Result<Option<Self>, IpccDataError> {
+        Self::from_slice_with(&d, opts)
+    }
+
+    /// Like [`PanicData::from_slice`], but with the specified options.
+    pub fn from_slice_with(
+        d: &[u8],
+        opts: &PanicDataOptions,
+    ) -> Result<Option<Self>, IpccDataError> {
+        Ok(Self::decode(d, opts)?.map(|(data, _, _)| data))
+    }
+
+    /// Like [`PanicData::from_bytes`], but for panic data of a version that
+    /// we don't know how to decode, returns degraded [`PanicData`] in which
+    /// only the fields common to all versions are valid.
+    pub fn from_bytes_best_effort(
+        d: Vec<u8>,
+    ) -> Result<Option<Self>, IpccDataError> {
+        Self::from_bytes_with(d, &PanicDataOptions::new().best_effort(true))
+    }
+
+    /// Like [`PanicData::from_bytes`], but requiring that the payload be
+    /// intact:  a payload that suffers from [hubris#1554] (and therefore
+    /// requires its version to be inferred and its cause to be
+    /// reconstructed) results in an error.
+    ///
+    /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+    ///
+    pub fn from_bytes_strict(
+        d: Vec<u8>,
+    ) -> Result<Option<Self>, IpccDataError> {
+        let opts = PanicDataOptions::new().require_determined_version(true);
+        Self::from_bytes_with(d, &opts)
+    }
+
+    /// Like [`PanicData::from_bytes`], but also returning the bytes that were
+    /// actually decoded -- which, in the presence of [hubris#1554], will
+    /// differ from those that were received.
+    ///
+    /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+    ///
+    pub fn from_bytes_with_raw(
+        d: Vec<u8>,
+    ) -> Result<Option<RawPanicData>, IpccDataError> {
+        Ok(Self::decode(&d, &PanicDataOptions::default())?.map(
+            |(data, fixed, _)| RawPanicData {
+                data,
+                fixed: fixed.into_owned(),
+            },
+        ))
+    }
+
+    /// Like [`PanicData::from_slice`], but also returning the number of
+    /// bytes of the payload that were decoded.  Any bytes beyond these (as
+    /// with a V1 payload that is longer than its fixed size) are either a
+    /// further payload or trailing junk.
+    pub fn from_bytes_counted(
+        d: &[u8],
+    ) -> Result<Option<(Self, usize)>, IpccDataError> {
+        Ok(Self::decode(d, &PanicDataOptions::default())?
+            .map(|(data, _, consumed)| (data, consumed)))
+    }
+
+    /// Decodes each of the panic payloads in the given buffer, as when
+    /// several payloads have been captured back to back.  Decoding stops at
+    /// the end of the buffer, or at a run of zeros extending to the end of
+    /// the buffer (which is taken to be padding).  If any payload fails to
+    /// decode -- including a partial payload at the end of the buffer, which
+    /// would otherwise be decoded as [truncated](PanicData::truncated) -- an
+    /// error is returned.
+    pub fn from_bytes_multi(d: &[u8]) -> Result<Vec<Self>, IpccDataError> {
+        let opts = PanicDataOptions::default();
+        let mut rval = vec![];
+        let mut d = d;
+
+        while let Some((data, fixed, consumed)) = Self::decode(d, &opts)? {
+            if data.truncated {
+                return Err(IpccDataError::PayloadTooShort {
+                    got: fixed.len(),
+                    need: IPCC_PANIC_V1_LEN,
+                });
+            }
+
+            rval.push(data);
+            d = &d[consumed.min(d.len())..];
+        }
+
+        Ok(rval)
+    }
+
+    //
+    // Decodes a single payload, returning the panic data, the (possibly
+    // fixed up) bytes from which it was decoded, and the number of bytes of
+    // the given payload that were consumed.
+    //
+    fn decode<'a>(
+        d: &'a [u8],
+        opts: &PanicDataOptions,
+    ) -> Result<Option<DecodedPanicData<'a>>, IpccDataError> {
+        if is_empty_payload(d) {
+            return Ok(None);
+        }
+
+        //
+        // The minimum length of a payload depends on its version, so it is
+        // left to the decoder for that version to check.
+        //
+        let (version, fixed) = fix_panic_data(d, opts)?;
+
+        if let PanicDataVersion::Inferred(n) = version {
+            if opts.require_determined_version {
+                return Err(IpccDataError::VersionNotDetermined(n));
+            }
+        }
+
+        //
+        // We can't know the length of a payload of a version that we don't
+        // know how to decode, so we take such a payload to be everything.
+        //
+        let (data, consumed) = match panic_decoder(version.number()) {
+            Some(decoder) => decoder(version, &fixed, opts)?,
+            None if opts.best_effort => {
+                (Self::from_header(version, &fixed, opts)?, fixed.len())
+            }
+            None => {
+                return Err(IpccDataError::UnsupportedVersion(version.number()))
+            }
+        };
+
+        //
+        // If we fixed up the payload, it is longer than what we were given.
+        //
+        let consumed = consumed.saturating_sub(fixed.len() - d.len());
+
+        Ok(Some((data, fixed, consumed)))
+    }
+
+    /// Like [`PanicData::from_bytes`], but taking the payload as a string of
+    /// hex bytes.  Bytes may be separated by whitespace and/or commas, and
+    /// may be prefixed with `0x`; a single hex digit is taken to be a byte,
+    /// and a run of hex digits without separators is taken to be a sequence
+    /// of bytes.
+    pub fn from_hex(s: &str) -> Result<Option<Self>, IpccDataError> {
+        let mut d = vec![];
+
+        for token in s.split(|c: char| c.is_whitespace() || c == ',') {
+            if token.is_empty() {
+                continue;
+            }
+
+            let invalid = || IpccDataError::InvalidHex(token.to_string());
+
+            let hex = token
+                .strip_prefix("0x")
+                .or_else(|| token.strip_prefix("0X"))
+                .unwrap_or(token);
+
+            if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+
+            if hex.len() == 1 {
+                d.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                continue;
+            }
+
+            if hex.len() % 2 != 0 {
+                return Err(invalid());
+            }
+
+            for ndx in (0..hex.len()).step_by(2) {
+                let byte = u8::from_str_radix(&hex[ndx..ndx + 2], 16)
+                    .map_err(|_| invalid())?;
+                d.push(byte);
+            }
+        }
+
+        Self::from_bytes(d)
+    }
+
+    /// Like [`PanicData::from_bytes`], but taking the payload as a base64
+    /// string.  Both the standard and URL-safe alphabets are accepted.
+    #[cfg(feature = "base64")]
+    pub fn from_base64(s: &str) -> Result<Option<Self>, IpccDataError> {
+        use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+        use base64::Engine;
+
+        let s = s.trim();
+
+        let d = match STANDARD.decode(s) {
+            Ok(d) => d,
+            Err(err) => URL_SAFE
+                .decode(s)
+                .map_err(|_| IpccDataError::InvalidBase64(err))?,
+        };
+
+        Self::from_bytes(d)
+    }
+
+    /// Like [`PanicData::from_bytes`], but reading the payload from the given
+    /// reader.  The payload is taken to be everything up to the end of the
+    /// stream; because the fixups for [hubris#1554] depend on the payload in
+    /// its entirety, it is buffered internally.
+    ///
+    /// [hubris#1554]: https://github.com/oxidecomputer/hubris/issues/1554
+    ///
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::Read>(
+        r: &mut R,
+    ) -> Result<Option<Self>, IpccDataError> {
+        let mut d = vec![];
+        r.read_to_end(&mut d).map_err(IpccDataError::Read)?;
+        Self::from_bytes(d)
+    }
+
+    /// Like [`PanicData::from_bytes`], but reading the payload from the file
+    /// at the given path (e.g., as dumped by Humility).  A failure to read
+    /// the file is returned as [`IpccDataError::ReadFile`], and is thereby
+    /// distinguished from a failure to decode its contents.
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<Option<Self>, IpccDataError> {
+        let path = path.as_ref();
+        let d = std::fs::read(path).map_err(|err| IpccDataError::ReadFile {
+            path: path.to_path_buf(),
+            err,
+        })?;
+        Self::from_bytes(d)
+    }
+
+    /// Serializes the panic data as a V2 payload.
+    ///
+    /// Decoding the result with [`PanicData::from_bytes`] yields the same raw
+    /// cause, error code, CPU, thread, addresses, message, stack and
+    /// ancillary data, as well as the same times and registers if they are
+    /// present (the raw registers are serialized too, though any decoded
+    /// registers take precedence over them).  The rest of the panic data
+    /// does not survive the round trip:
+    ///
+    /// - the version is always `Determined(2)`, so an inferred version is
+    ///   lost, as is `cause_inferred` (though the raw cause, including any
+    ///   reconstructed low byte, is kept);
+    /// - absent registers are written as zero (unless the raw registers are
+    ///   present), and so come back as zero registers unless the panic is
+    ///   due to an explicit call to panic;
+    /// - symbols resolved for addresses other than those of the stack (that
+    ///   is, `addr_symbols`) are not kept;
+    /// - the `stack_clamped`, `stack_truncated`, `time_invalid`,
+    ///   `truncated`, `items_len_mismatch`, `nop_data` and `degraded` flags
+    ///   describe the payload from which the panic data was decoded, and are
+    ///   not kept.
+    pub fn to_v2_bytes(&self) -> Result<Vec<u8>, IpccDataError> {
+        let u16_of = |field: &'static str, got: usize| {
+            u16::try_from(got)
+                .map_err(|_| IpccDataError::SerializeOverflow { field, got })
+        };
+
+        let item = |ftype, data: Vec<u8>| -> Result<_, IpccDataError> {
+            Ok(IpccPanicItem {
+                ftype,
+                len: u16_of("item", data.len() + 3)?,
+                data,
+            })
+        };
+
+        let mut items = vec![];
+
+        if let Some(message) = &self.message {
+            let data = message.as_bytes().to_vec();
+            items.push(item(IpccPanicItemType::Message, data)?);
+        }
+
+        for frame in &self.stack {
+            let ps = IpccPanicStack {
+                addr: frame.address.0,
+                offset: frame.offset,
+                symbol: match &frame.symbol {
+                    Some(symbol) => symbol.as_bytes().to_vec(),
+                    None => vec![],
+                },
+            };
+
+            let mut cursor = Cursor::new(vec![]);
+            ps.write_le(&mut cursor).map_err(IpccDataError::Serialize)?;
+            items.push(item(
+                IpccPanicItemType::StackEntry,
+                cursor.into_inner(),
+            )?);
+        }
+
+        for data in &self.ancillary {
+            items.push(item(IpccPanicItemType::Ancillary, data.clone())?);
+        }
+
+        let mut registers = self.raw_registers.unwrap_or_default();
+
+        if let Some(r) = &self.registers {
+            for (&reg, &val) in r {
+                registers.set(reg, val);
+            }
+        }
+
+        let items_len: usize = items.iter().map(|i| usize::from(i.len)).sum();
+
+        let p = IpccPanicDataV2 {
+            version: IpccPanicDataV2::VERSION,
+            cause: self.raw_cause,
+            error: self.error_code,
+            hrtime: self.hrtime.as_ref().map_or(0, |t| t.0),
+            hrestime: match &self.time {
+                Some(time) => IpccHresTime {
+                    tv_sec: time.sec,
+                    tv_nsec: time.nsec.into(),
+                },
+                None => IpccHresTime {
+                    tv_sec: 0,
+                    tv_nsec: 0,
+                },
+            },
+            cpuid: self.cpuid.0,
+            thread: self.thread.0,
+            addr: self.addr.0,
+            pc: self.pc.0,
+            fp: self.fp.0,
+            rp: self.rp.0,
+            registers,
+            nitems: u16_of("item count", items.len())?,
+            items_len: u16_of("items length", items_len)?,
+            items,
+        };
+
+        let mut cursor = Cursor::new(vec![]);
+        p.write_le(&mut cursor).map_err(IpccDataError::Serialize)?;
+
+        Ok(cursor.into_inner())
+    }
+
+    /// Returns the version number of the panic data.
+    pub fn version_number(&self) -> u8 {
+        self.version.number()
+    }
+
+    /// Returns true if the version of the panic data had to be inferred.
+    pub fn version_was_inferred(&self) -> bool {
+        self.version.is_inferred()
+    }
+
+    /// Returns the value of the given register, or `None` if the registers
+    /// are not present.
+    pub fn register(&self, reg: Register) -> Option<u64> {
+        self.registers.as_ref()?.get(&reg).copied()
+    }
+
+    /// Returns the registers exactly as they appear in the payload (including
+    /// `savfp` and `savpc`), or `None` if the registers are not present.
+    pub fn raw_registers(&self) -> Option<&RawRegisters> {
+        self.raw_registers.as_ref()
+    }
+
+    /// Returns those registers (in the order of [`Register::all`]) that are
+    /// absent from the decoded registers.  For well-formed panic data, this
+    /// is empty; if the registers are not present at all (as for a panic due
+    /// to an explicit call to panic), it is also empty.
+    pub fn missing_registers(&self) -> Vec<Register> {
+        match &self.registers {
+            Some(registers) => Register::all()
+                .iter()
+                .filter(|r| !registers.contains_key(*r))
+                .copied()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the panic data flattened into names and values, as for
+    /// telemetry.  The names are stable:  `version`, `version_inferred`,
+    /// `cause`, `error`, `cpuid`, `thread`, `addr`, `pc`, `fp` and `rp` are
+    /// always present; `hrtime`, `time` and `message` are present if known;
+    /// registers are present as `reg.rip` (etc.) if known; and `stack` is
+    /// present (with its frames separated by commas) if the stack is not
+    /// empty.  Numeric values other than the version, CPU ID and times are
+    /// in hexadecimal.
+    pub fn to_fields(&self) -> Fields {
+        let mut fields = Fields::default();
+
+        let mut add = |name: &str, value: String| {
+            fields.insert(name.to_string(), value);
+        };
+
+        add("version", self.version_number().to_string());
+        add("version_inferred", self.version_was_inferred().to_string());
+        add("cause", self.cause.to_string());
+        add("error", format!("{:#x}", self.error_code));
+        add("cpuid", self.cpuid.to_string());
+        add("thread", format!("{:#x}", self.thread));
+        add("addr", format!("{:#x}", self.addr));
+        add("pc", format!("{:#x}", self.pc));
+        add("fp", format!("{:#x}", self.fp));
+        add("rp", format!("{:#x}", self.rp));
+
+        if let Some(hrtime) = &self.hrtime {
+            add("hrtime", hrtime.to_string());
+        }
+
+        if let Some(time) = &self.time {
+            add("time", format!("{}.{:09}", time.sec, time.nsec));
+        }
+
+        if let Some(message) = &self.message {
+            add("message", message.clone());
+        }
+
+        if let Some(registers) = &self.registers {
+            for (reg, val) in registers {
+                add(&format!("reg.{reg}"), format!("{val:#x}"));
+            }
+        }
+
+        if !self.stack.is_empty() {
+            let stack = self
+                .stack
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            add("stack", stack.join(","));
+        }
+
+        fields
+    }
+
+    /// Returns the panic data as a JSON object, without requiring the
+    /// `serde` feature.  Addresses, the error code and register values are
+    /// hexadecimal strings; registers are keyed by their name (e.g.,
+    /// `"rdi"`); absent values are `null`.
+    #[cfg(feature = "json")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        use serde_json::{json, Map, Value};
+
+        let hex = |addr: Addr| Value::String(format!("{:#x}", addr));
+
+        let registers = self.registers.as_ref().map(|registers| {
+            registers
+                .iter()
+                .map(|(reg, val)| (reg.to_string(), json!(format!("{val:#x}"))))
+                .collect::<Map<_, _>>()
+        });
+
+        let stack = self
+            .stack
+            .iter()
+            .map(|frame| {
+                json!({
+                    "address": hex(frame.address),
+                    "symbol": frame.symbol,
+                    "offset": format!("{:#x}", frame.offset),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "version": self.version_number(),
+            "version_inferred": self.version_was_inferred(),
+            "cause": self.cause.to_string(),
+            "error": format!("{:#x}", self.error_code),
+            "cpuid": self.cpuid.0,
+            "hrtime": self.hrtime.map(|hrtime| hrtime.0),
+            "time": self.time.as_ref().map(|time| {
+                json!({ "sec": time.sec, "nsec": time.nsec })
+            }),
+            "thread": hex(self.thread),
+            "addr": hex(self.addr),
+            "pc": hex(self.pc),
+            "fp": hex(self.fp),
+            "rp": hex(self.rp),
+            "message": self.message,
+            "registers": registers,
+            "stack": stack,
+        })
+    }
+
+    /// Returns the registers formatted as they are by dumpregs() in the
+    /// operating system:  three to a line, each with a three character label
+    /// and a space-padded hexadecimal value.  If the registers are not
+    /// present, returns an empty string.
+    pub fn format_registers(&self) -> String {
+        let registers = match &self.registers {
+            Some(registers) => registers,
+            None => return String::new(),
+        };
 
-            match version.number() {
-                1 => Ok(Some(Self::from_v1(version, data)?)),
-                2 => Ok(Some(Self::from_v2(version, data)?)),
-                n => {
-                    bail!("unsupported IPCC panic data version: {n}");
+        let mut rval = String::new();
+
+        for (ndx, (reg, val)) in registers.iter().enumerate() {
+            let label = match reg {
+                Register::fsbase => "fsb".to_string(),
+                Register::gsbase => "gsb".to_string(),
+                Register::trapno => "trp".to_string(),
+                reg => reg.to_string(),
+            };
+
+            rval.push_str(&match ndx % 3 {
+                0 => format!("\t{label:>3}: {val:16x}"),
+                _ => format!(" {label:>3}: {val:16x}"),
+            });
+
+            if ndx % 3 == 2 || ndx == registers.len() - 1 {
+                rval.push('\n');
+            }
+        }
+
+        rval
+    }
+
+    /// If the panic was due to a trap, returns the trap as decoded from the
+    /// `trapno` register.  If the panic was not due to a trap (or if the
+    /// registers are not present), returns `None`.
+    pub fn trap(&self) -> Option<Trap> {
+        if !self.cause.is_trap() {
+            return None;
+        }
+
+        Some(Trap::from(self.register(Register::trapno)?))
+    }
+
+    /// If the panic was due to a page fault, returns the page fault error
+    /// code as decoded from the `err` register.  If the panic was not due to
+    /// a page fault (or if the registers are not present), returns `None`.
+    pub fn page_fault_info(&self) -> Option<PageFaultFlags> {
+        if self.trap()? != Trap::PageFault {
+            return None;
+        }
+
+        let err = self.register(Register::err)?;
+        Some(PageFaultFlags::from_bits_retain(err))
+    }
+
+    /// Returns the flags register as decoded from the `rfl` register, or
+    /// `None` if the registers are not present.
+    pub fn rflags(&self) -> Option<RFlags> {
+        let rfl = self.register(Register::rfl)?;
+        Some(RFlags::from_bits_retain(rfl))
+    }
+
+    /// If the panic was due to a trap, returns the address associated with
+    /// the trap (e.g., the faulting address of a page fault); otherwise,
+    /// returns `None`.
+    pub fn faulting_address(&self) -> Option<Addr> {
+        match self.cause.is_trap() {
+            true => Some(self.addr),
+            false => None,
+        }
+    }
+
+    /// Checks that the stack is consistent with the program counter and
+    /// register pointer in the header, as an indication of whether the
+    /// panic data is intact.  For a panic due to a trap, the program counter
+    /// must be that of the trap (that is, `rip`, if the registers are
+    /// present) and, if there is a stack, must be the address of one of its
+    /// innermost few frames; the register pointer must be non-null and must
+    /// lie between the frame pointer and the saved frame pointer (if the
+    /// latter is present and non-null).  For any panic, no frame of the
+    /// stack may have a null address (unless it has a symbol).
+    pub fn validate_stack_consistency(&self) -> Result<(), IpccDataError> {
+        let fail = |inconsistency| {
+            Err(IpccDataError::StackInconsistent(inconsistency))
+        };
+
+        if self.cause.is_trap() {
+            if let Some(rip) = self.register(Register::rip) {
+                if rip != self.pc.0 {
+                    return fail("program counter differs from rip");
                 }
             }
+
+            let top = &self.stack[..self.stack.len().min(TRAP_PC_FRAMES)];
+
+            if !top.is_empty() && !top.iter().any(|f| f.address == self.pc) {
+                return fail("program counter not in innermost stack frames");
+            }
+
+            if self.rp.0 == 0 {
+                return fail("register pointer is null for trap");
+            }
+
+            if self.rp.0 < self.fp.0 {
+                return fail("register pointer is below frame pointer");
+            }
+
+            let savfp = self.raw_registers.map_or(0, |r| r.savfp);
+
+            if savfp != 0 && self.rp.0 >= savfp {
+                return fail("register pointer is beyond saved frame pointer");
+            }
+        }
+
+        let null = |f: &StackFrame| f.address.0 == 0 && !f.is_symbolicated();
+
+        if self.stack.iter().any(null) {
+            return fail("stack frame has null address and no symbol");
         }
+
+        Ok(())
     }
-}
 
-/// Image boot header
-///
-/// See `oxide_boot_sp.h` for the equivalent C definition
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    zerocopy::FromBytes,
-    zerocopy::IntoBytes,
-    zerocopy::KnownLayout,
-    zerocopy::Immutable,
-)]
-#[repr(C)]
-pub struct BootSpHeader {
-    pub magic: u32,
-    pub version: u32,
+    /// Returns the privilege level (ring) at the time of the panic, as
+    /// determined by the low two bits of the `cs` register -- or `None` if
+    /// the registers are not present.
+    pub fn privilege_level(&self) -> Option<u8> {
+        let cs = self.register(Register::cs)?;
+        Some((cs & 0b11) as u8)
+    }
 
-    pub flags: u64,
-    pub data_size: u64,
-    pub image_size: u64,
-    pub target_size: u64,
+    /// Returns true if the panic occurred in user mode (that is, in ring 3),
+    /// as determined by the `cs` register -- or `None` if the registers are
+    /// not present.
+    pub fn is_user_mode(&self) -> Option<bool> {
+        Some(self.privilege_level()? == 3)
+    }
 
-    pub sha256: [u8; 32],
+    /// Symbolizes the stack using the given resolver, which is passed the
+    /// address of each frame and returns its symbol and offset (if known).
+    /// The thread, program counter, frame pointer and register pointer are
+    /// resolved as well (see [`AddrSymbols`] for which of these can be
+    /// expected to resolve).  Frames and addresses that already have a symbol
+    /// are left alone unless `force` is set.
+    pub fn symbolize<F>(&mut self, resolve: F, force: bool)
+    where
+        F: Fn(Addr) -> Option<(String, u64)>,
+    {
+        for frame in self.stack.iter_mut() {
+            if frame.symbol.is_some() && !force {
+                continue;
+            }
 
-    pub dataset: [u8; 128],
-    pub imagename: [u8; 128],
-}
+            if let Some((symbol, offset)) = resolve(frame.address) {
+                frame.symbol = Some(symbol);
+                frame.offset = offset;
+            }
+        }
 
-impl BootSpHeader {
-    pub const MAGIC: u32 = 0x1DEB0075;
-    pub const VERSION: u32 = 2;
-    pub const HEADER_SIZE: usize = 0x1000;
-    pub const FLAG_COMPRESSED: u32 = 0x1;
+        let syms = &mut self.addr_symbols;
+
+        for (addr, sym) in [
+            (self.thread, &mut syms.thread),
+            (self.pc, &mut syms.pc),
+            (self.fp, &mut syms.fp),
+            (self.rp, &mut syms.rp),
+        ] {
+            if sym.is_some() && !force {
+                continue;
+            }
+
+            if let Some(resolved) = resolve(addr) {
+                *sym = Some(resolved);
+            }
+        }
+    }
+
+    /// Returns the symbol of the panicking thread, if it has been resolved.
+    pub fn thread_symbol(&self) -> Option<&str> {
+        self.addr_symbols.thread.as_ref().map(|(s, _)| s.as_str())
+    }
+
+    /// Returns the symbol of the program counter, if it has been resolved.
+    pub fn pc_symbol(&self) -> Option<&str> {
+        self.addr_symbols.pc.as_ref().map(|(s, _)| s.as_str())
+    }
+
+    /// Returns the symbol of the frame pointer, if it has been resolved.
+    pub fn fp_symbol(&self) -> Option<&str> {
+        self.addr_symbols.fp.as_ref().map(|(s, _)| s.as_str())
+    }
+
+    /// Returns the symbol of the register pointer, if it has been resolved.
+    pub fn rp_symbol(&self) -> Option<&str> {
+        self.addr_symbols.rp.as_ref().map(|(s, _)| s.as_str())
+    }
+
+    /// Returns a signature of the panic suitable for grouping identical
+    /// panics together, consisting of a hash of the cause and the symbols of
+    /// the stack (or the addresses of frames without symbols).  Offsets are
+    /// not considered.  The hash is 64-bit FNV-1a, and is therefore stable
+    /// across runs, machines and versions of this crate.
+    pub fn stack_signature(&self) -> u64 {
+        use core::hash::Hasher;
+
+        let mut hasher = FnvHasher::default();
+        let mut feed = |bytes: &[u8]| hasher.write(bytes);
+
+        feed(&self.raw_cause.to_le_bytes());
+
+        //
+        // We tag each frame (and length-prefix each symbol) so that
+        // different stacks can't be made to look alike by concatenation.
+        //
+        for frame in &self.stack {
+            match &frame.symbol {
+                Some(symbol) => {
+                    feed(&[0]);
+                    feed(&(symbol.len() as u64).to_le_bytes());
+                    feed(symbol.as_bytes());
+                }
+                None => {
+                    feed(&[1]);
+                    feed(&frame.address.0.to_le_bytes());
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Returns the panic message (if any) without copying it.
+    pub fn message_str(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Replaces the panic message (if any) with the result of applying the
+    /// given redactor to it, allowing sensitive strings to be scrubbed
+    /// according to the caller's policy.
+    pub fn redact_message<F>(&mut self, redactor: F)
+    where
+        F: Fn(&str) -> String,
+    {
+        if let Some(message) = &self.message {
+            self.message = Some(redactor(message));
+        }
+    }
+
+    /// Redacts the panic message with a simple default policy:  any
+    /// whitespace-delimited word that looks like an absolute path (that is,
+    /// that starts with `/`, perhaps after an opening quote or parenthesis)
+    /// or an e-mail address (that is, that contains `@`) is replaced with
+    /// `<redacted>`.
+    pub fn redact_message_default(&mut self) {
+        self.redact_message(|message| {
+            let mut rval = String::with_capacity(message.len());
+
+            for piece in message.split_inclusive(char::is_whitespace) {
+                let word = piece.trim_end_matches(char::is_whitespace);
+                let bare = word.trim_start_matches(&['"', '\'', '('][..]);
+
+                if bare.starts_with('/') || word.contains('@') {
+                    rval.push_str("<redacted>");
+                    rval.push_str(&piece[word.len()..]);
+                } else {
+                    rval.push_str(piece);
+                }
+            }
+
+            rval
+        });
+    }
+
+    /// Returns a one-line summary of the panic consisting of its cause, the
+    /// panicking CPU, the innermost frame of the stack (if any) and the head
+    /// of the message (if any), e.g.:
+    ///
+    /// ```text
+    /// IPCC_PANIC_TRAP cpu 3 at mutex_panic+0x1c: "recursive mutex enter"
+    /// ```
+    pub fn summary(&self) -> String {
+        let mut rval = format!("{} cpu {}", self.cause, self.cpuid);
+
+        if let Some(frame) = self.top_frame() {
+            rval.push_str(&format!(" at {}", frame));
+        }
+
+        if let Some(message) = &self.message {
+            let mut head: String =
+                message.chars().take(SUMMARY_MSGLEN).collect();
+
+            if head.len() < message.len() {
+                head.push_str("...");
+            }
+
+            rval.push_str(&format!(": {:?}", head));
+        }
+
+        rval
+    }
+
+    /// Returns the innermost frame of the stack (that is, the first), if
+    /// any.
+    pub fn top_frame(&self) -> Option<&StackFrame> {
+        self.stack.first()
+    }
+
+    /// Returns the symbol of the innermost frame of the stack, if there is
+    /// such a frame and it has a symbol.
+    pub fn top_symbol(&self) -> Option<&str> {
+        self.top_frame()?.symbol.as_deref()
+    }
+
+    /// Returns the number of frames in the stack.
+    pub fn stack_depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the number of frames in the stack that have a symbol.
+    pub fn symbolicated_frame_count(&self) -> usize {
+        self.stack.iter().filter(|f| f.is_symbolicated()).count()
+    }
+
+    /// Returns true if every frame in the stack has a symbol (including,
+    /// vacuously, if the stack is empty).
+    pub fn is_fully_symbolicated(&self) -> bool {
+        self.stack.iter().all(StackFrame::is_symbolicated)
+    }
+
+    /// Returns the stack as lines suitable for display, each consisting of
+    /// the depth of the frame, the frame itself and its address, e.g.
+    /// `#0  die+0x43  0xfffffffffbc3a1d0`.  Columns are aligned across
+    /// lines.
+    pub fn stack_lines(&self) -> impl Iterator<Item = String> + '_ {
+        let depth = self.stack.len().saturating_sub(1).to_string().len();
+
+        let width = self
+            .stack
+            .iter()
+            .map(|frame| frame.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        self.stack.iter().enumerate().map(move |(ndx, frame)| {
+            format!("#{ndx:<depth$}  {frame:width$}  {:#018x}", frame.address)
+        })
+    }
+
+    /// Returns the stack with runs of consecutive identical frames (as from
+    /// runaway recursion) collapsed into a single frame with a repeat count.
+    /// Frames are considered identical if they have the same symbol and
+    /// offset (or, if either lacks a symbol, the same address).
+    pub fn collapsed_stack(&self) -> Vec<CollapsedFrame<'_>> {
+        let mut collapsed: Vec<CollapsedFrame<'_>> = vec![];
+
+        for frame in &self.stack {
+            match collapsed.last_mut() {
+                Some(last) if last.frame.matches(frame) => last.repeat += 1,
+                _ => collapsed.push(CollapsedFrame { frame, repeat: 1 }),
+            }
+        }
+
+        collapsed
+    }
+
+    /// Compares this panic to another, returning a description of how
+    /// `other` differs from it.  Stack frames are considered to be the same
+    /// if they have the same symbol and offset (or, if either lacks a
+    /// symbol, the same address), allowing panics from different builds to
+    /// be meaningfully compared.
+    pub fn diff(&self, other: &PanicData) -> PanicDiff {
+        let cause = match self.cause == other.cause {
+            true => None,
+            false => Some((self.cause, other.cause)),
+        };
+
+        let cpuid = match self.cpuid == other.cpuid {
+            true => None,
+            false => Some((self.cpuid, other.cpuid)),
+        };
+
+        let registers = Register::all()
+            .iter()
+            .filter_map(|&register| {
+                let (old, new) =
+                    (self.register(register), other.register(register));
+
+                match old == new {
+                    true => None,
+                    false => Some(RegisterDiff { register, old, new }),
+                }
+            })
+            .collect();
+
+        let common = self
+            .stack
+            .iter()
+            .zip(other.stack.iter())
+            .take_while(|(lhs, rhs)| lhs.matches(rhs))
+            .count();
+
+        let stack_divergence =
+            match common == self.stack.len() && common == other.stack.len() {
+                true => None,
+                false => Some(common),
+            };
+
+        PanicDiff {
+            cause,
+            cpuid,
+            registers,
+            stack_divergence,
+        }
+    }
+}
+
+/// A register whose value differs between two panics; see [`PanicDiff`].
+/// A value of `None` denotes that the register was not present.
+#[derive(Debug)]
+pub struct RegisterDiff {
+    pub register: Register,
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+}
+
+/// The differences between two panics, as returned by [`PanicData::diff`].
+#[derive(Debug)]
+pub struct PanicDiff {
+    /// old and new cause, if different
+    pub cause: Option<(PanicCause, PanicCause)>,
+
+    /// old and new panicking CPU, if different
+    pub cpuid: Option<(Cpuid, Cpuid)>,
+
+    /// registers that differ, in dumpregs() order
+    pub registers: Vec<RegisterDiff>,
+
+    /// depth of the first stack frame that differs, if the stacks differ
+    pub stack_divergence: Option<usize>,
+}
+
+impl PanicDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.cause.is_none()
+            && self.cpuid.is_none()
+            && self.registers.is_empty()
+            && self.stack_divergence.is_none()
+    }
+}
+
+impl core::fmt::Display for PanicDiff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+
+        if let Some((old, new)) = &self.cause {
+            writeln!(f, "{:>12} {} -> {}", "cause:", old, new)?;
+        }
+
+        if let Some((old, new)) = &self.cpuid {
+            writeln!(f, "{:>12} {} -> {}", "cpuid:", old, new)?;
+        }
+
+        let value = |val: &Option<u64>| match val {
+            Some(val) => format!("{val:016x}"),
+            None => format!("{:>16}", "-"),
+        };
+
+        for diff in &self.registers {
+            let reg = format!("%{}:", diff.register);
+            writeln!(
+                f,
+                "{reg:>12} {} -> {}",
+                value(&diff.old),
+                value(&diff.new)
+            )?;
+        }
+
+        if let Some(depth) = self.stack_divergence {
+            writeln!(f, "{:>12} diverges at frame {}", "stack:", depth)?;
+        }
+
+        Ok(())
+    }
+}
+
+//
+// The conversions are in terms of `from_slice` and `from_bytes`, with the
+// absence of panic data (that is, an empty or zero payload) being an error.
+// (`Option<PanicData>` can't be the target of the conversion, as it isn't
+// local to this crate.)
+//
+impl TryFrom<&[u8]> for PanicData {
+    type Error = IpccDataError;
+
+    fn try_from(d: &[u8]) -> Result<Self, Self::Error> {
+        Self::from_slice(d)?.ok_or(IpccDataError::EmptyPayload)
+    }
+}
+
+impl TryFrom<Vec<u8>> for PanicData {
+    type Error = IpccDataError;
+
+    fn try_from(d: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::from_bytes(d)?.ok_or(IpccDataError::EmptyPayload)
+    }
+}
+
+//
+// `IndexMap` doesn't implement `Hash`, so we must implement it ourselves.
+// Because equality of the registers doesn't consider their order, neither
+// can their hash:  we hash them in the canonical (dumpregs()) order.
+//
+impl core::hash::Hash for PanicData {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        let PanicData {
+            version,
+            cause,
+            cause_inferred,
+            raw_cause,
+            error_code,
+            cpuid,
+            hrtime,
+            time,
+            time_invalid,
+            thread,
+            addr,
+            pc,
+            fp,
+            rp,
+            addr_symbols,
+            message,
+            registers,
+            raw_registers,
+            stack,
+            stack_clamped,
+            stack_truncated,
+            ancillary,
+            truncated,
+            items_len_mismatch,
+            nop_data,
+            degraded,
+        } = self;
+
+        version.hash(state);
+        cause.hash(state);
+        cause_inferred.hash(state);
+        raw_cause.hash(state);
+        error_code.hash(state);
+        cpuid.hash(state);
+        hrtime.hash(state);
+        time.hash(state);
+        time_invalid.hash(state);
+        thread.hash(state);
+        addr.hash(state);
+        pc.hash(state);
+        fp.hash(state);
+        rp.hash(state);
+        addr_symbols.hash(state);
+        message.hash(state);
+
+        registers.is_some().hash(state);
+
+        if let Some(registers) = registers {
+            registers.len().hash(state);
+
+            for reg in Register::all() {
+                registers.get(reg).hash(state);
+            }
+        }
+
+        raw_registers.hash(state);
+        stack.hash(state);
+        stack_clamped.hash(state);
+        stack_truncated.hash(state);
+        ancillary.hash(state);
+        truncated.hash(state);
+        items_len_mismatch.hash(state);
+        nop_data.hash(state);
+        degraded.hash(state);
+    }
+}
+
+//
+// The report that we render for a panic is modelled on the panic output in
+// mdb and Humility:  the cause and the panicking CPU and thread, followed by
+// the times (if known), the message (if any), the registers (if any) and the
+// stack.  Registers are rendered as by `format_registers` -- which is to say,
+// as by dumpregs().
+//
+impl core::fmt::Display for PanicData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "{:>12} {}", "version:", self.version)?;
+        writeln!(f, "{:>12} {}", "cause:", self.cause)?;
+        writeln!(f, "{:>12} {:#x}", "error:", self.error_code)?;
+        writeln!(f, "{:>12} {}", "cpuid:", self.cpuid)?;
+        writeln!(f, "{:>12} {:#x}", "thread:", self.thread)?;
+        writeln!(f, "{:>12} {:#x}", "addr:", self.addr)?;
+        writeln!(f, "{:>12} {:#x}", "pc:", self.pc)?;
+        writeln!(f, "{:>12} {:#x}", "fp:", self.fp)?;
+        writeln!(f, "{:>12} {:#x}", "rp:", self.rp)?;
+
+        if let Some(hrtime) = &self.hrtime {
+            writeln!(f, "{:>12} {}", "hrtime:", hrtime)?;
+        }
+
+        if let Some(time) = &self.time {
+            writeln!(f, "{:>12} {}.{:09}", "time:", time.sec, time.nsec)?;
+        }
+
+        if let Some(message) = &self.message {
+            writeln!(f, "{:>12} {}", "message:", message)?;
+        }
+
+        if self.registers.is_some() {
+            writeln!(f)?;
+            write!(f, "{}", self.format_registers())?;
+        }
+
+        if !self.stack.is_empty() {
+            let width = self
+                .stack
+                .iter()
+                .map(|frame| frame.to_string().len())
+                .max()
+                .unwrap_or(0);
+
+            writeln!(f)?;
+
+            for frame in &self.stack {
+                writeln!(f, "    {frame:width$} {:#018x}", frame.address)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The cause of a host boot failure, as denoted by the `IPCC_BOOTFAIL_*`
+/// values of [`kernel_ipcc.h`].
+///
+/// [`kernel_ipcc.h`]: https://github.com/oxidecomputer/illumos-gate/blob/stlouis/usr/src/uts/oxide/sys/kernel_ipcc.h
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BootFailCause {
+    /// Generic boot failure
+    Generic,
+
+    /// No phase 2 image was found
+    NoPhase2,
+
+    /// The phase 2 image header was invalid
+    Header,
+
+    /// The phase 2 image failed its integrity check
+    Integrity,
+
+    /// The ramdisk could not be mounted
+    Ramdisk,
+
+    /// A hardware error was encountered
+    HwError,
+
+    /// Corrupt or otherwise unrecognized cause
+    Unknown(u8),
+}
+
+impl From<u8> for BootFailCause {
+    fn from(cause: u8) -> Self {
+        match cause {
+            0x01 => Self::Generic,
+            0x02 => Self::NoPhase2,
+            0x03 => Self::Header,
+            0x04 => Self::Integrity,
+            0x05 => Self::Ramdisk,
+            0x06 => Self::HwError,
+            c => Self::Unknown(c),
+        }
+    }
+}
+
+impl core::fmt::Display for BootFailCause {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Generic => "IPCC_BOOTFAIL_GENERIC".to_owned(),
+                Self::NoPhase2 => "IPCC_BOOTFAIL_NOPHASE2".to_owned(),
+                Self::Header => "IPCC_BOOTFAIL_HEADER".to_owned(),
+                Self::Integrity => "IPCC_BOOTFAIL_INTEGRITY".to_owned(),
+                Self::Ramdisk => "IPCC_BOOTFAIL_RAMDISK".to_owned(),
+                Self::HwError => "IPCC_BOOTFAIL_HWERROR".to_owned(),
+                Self::Unknown(c) => format!("<Unknown cause {c:#04x}>"),
+            }
+        )
+    }
+}
+
+/// Host boot failure data, the payload that corresponds to `HSSBootFail` as
+/// described in [RFD 316] and implemented in [`kernel_ipcc.h`].
+///
+/// [RFD 316]: https://rfd.shared.oxide.computer/rfd/0316
+/// [`kernel_ipcc.h`]: https://github.com/oxidecomputer/illumos-gate/blob/stlouis/usr/src/uts/oxide/sys/kernel_ipcc.h
+///
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BootFailData {
+    /// cause of boot failure
+    pub cause: BootFailCause,
+
+    /// boot failure message, if any
+    pub message: Option<String>,
+}
+
+// As with the panic data, this is defined in
+// usr/src/uts/oxide/sys/kernel_ipcc.h.
+const IPCC_BOOTFAIL_MAX_PAYLOAD: usize = 0x100;
+
+#[derive(Debug, BinRead)]
+struct IpccBootFail {
+    ibf_cause: u8,
+    #[br(parse_with = until_eof)]
+    ibf_message: Vec<u8>,
+}
+
+impl BootFailData {
+    /// Infer an IPCC data payload to be `HSSBootFail`, creating
+    /// [`BootFailData`] from received bytes.  As with
+    /// [`PanicData::from_bytes`], an all-zero payload indicates that there
+    /// is no boot failure, and results in `None`.
+    pub fn from_bytes(d: Vec<u8>) -> Result<Option<Self>, IpccDataError> {
+        if is_empty_payload(&d) {
+            return Ok(None);
+        }
+
+        if d.len() > IPCC_BOOTFAIL_MAX_PAYLOAD {
+            return Err(IpccDataError::BootFailTooLong {
+                got: d.len(),
+                max: IPCC_BOOTFAIL_MAX_PAYLOAD,
+            });
+        }
+
+        let mut cursor = Cursor::new(d);
+        let b = IpccBootFail::read_le(&mut cursor)
+            .map_err(IpccDataError::BootFailDeserialize)?;
+
+        let message = String::from_utf8_lossy(&b.ibf_message)
+            .trim_matches('\0')
+            .to_string();
+
+        Ok(Some(Self {
+            cause: b.ibf_cause.into(),
+            message: match message.len() {
+                0 => None,
+                _ => Some(message),
+            },
+        }))
+    }
+}
+
+bitflags::bitflags! {
+    /// Flags in a [`BootSpHeader`]
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct BootSpFlags: u64 {
+        /// Image is compressed
+        const COMPRESSED = BootSpHeader::FLAG_COMPRESSED as u64;
+
+        // Flags that we don't know about are nonetheless retained.
+        const _ = !0;
+    }
+}
+
+/// Image boot header
+///
+/// See `oxide_boot_sp.h` for the equivalent C definition
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    zerocopy::FromBytes,
+    zerocopy::IntoBytes,
+    zerocopy::KnownLayout,
+    zerocopy::Immutable,
+)]
+#[repr(C)]
+pub struct BootSpHeader {
+    pub magic: u32,
+    pub version: u32,
+
+    pub flags: u64,
+    pub data_size: u64,
+    pub image_size: u64,
+    pub target_size: u64,
+
+    pub sha256: [u8; 32],
+
+    pub dataset: [u8; 128],
+    pub imagename: [u8; 128],
+}
+
+impl BootSpHeader {
+    pub const MAGIC: u32 = 0x1DEB0075;
+    pub const VERSION: u32 = 2;
+    pub const HEADER_SIZE: usize = 0x1000;
+    pub const FLAG_COMPRESSED: u32 = 0x1;
+
+    /// Creates a header with the given dataset and image names, and with
+    /// the magic and version set to [`BootSpHeader::MAGIC`] and
+    /// [`BootSpHeader::VERSION`]; all other fields are zero.  The names are
+    /// NUL-padded, and must each fit within their fixed-size fields.
+    pub fn new(dataset: &str, imagename: &str) -> Result<Self, IpccDataError> {
+        fn pack(
+            field: &'static str,
+            s: &str,
+        ) -> Result<[u8; 128], IpccDataError> {
+            let mut buf = [0u8; 128];
+
+            if s.len() > buf.len() {
+                return Err(IpccDataError::BootSpHeaderFieldTooLong {
+                    field,
+                    got: s.len(),
+                    max: buf.len(),
+                });
+            }
+
+            buf[..s.len()].copy_from_slice(s.as_bytes());
+            Ok(buf)
+        }
+
+        Ok(Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            flags: 0,
+            data_size: 0,
+            image_size: 0,
+            target_size: 0,
+            sha256: [0; 32],
+            dataset: pack("dataset", dataset)?,
+            imagename: pack("imagename", imagename)?,
+        })
+    }
+
+    /// Serializes the header, padded with zeros to
+    /// [`BootSpHeader::HEADER_SIZE`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        use zerocopy::IntoBytes;
+
+        let mut buf = self.as_bytes().to_vec();
+        buf.resize(Self::HEADER_SIZE, 0);
+        buf
+    }
+
+    /// Returns the header's flags.  Any bits that we don't know about are
+    /// preserved.
+    pub fn flags(&self) -> BootSpFlags {
+        BootSpFlags::from_bits_retain(self.flags)
+    }
+
+    /// Returns true if the image is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.flags().contains(BootSpFlags::COMPRESSED)
+    }
+
+    /// Returns the offset of the image (compressed or not) within a blob
+    /// that consists of the header followed by the image, which is always
+    /// [`BootSpHeader::HEADER_SIZE`].
+    pub fn image_offset(&self) -> usize {
+        Self::HEADER_SIZE
+    }
+
+    /// Returns the range of the image within a blob that consists of the
+    /// header followed by the image, as computed from the image size.  If
+    /// the image is compressed, this is the range that it occupies once
+    /// inflated; the range of the compressed image as it follows the header
+    /// is given by [`BootSpHeader::data_range`].  The range is not checked
+    /// against any actual blob.
+    pub fn image_range(&self) -> core::ops::Range<usize> {
+        Self::range_of(self.image_size)
+    }
+
+    /// Returns the range of the data (that is, the image as it follows the
+    /// header, compressed or not) within a blob that consists of the header
+    /// followed by the image, as computed from the data size.  The range is
+    /// not checked against any actual blob.
+    pub fn data_range(&self) -> core::ops::Range<usize> {
+        Self::range_of(self.data_size)
+    }
+
+    fn range_of(size: u64) -> core::ops::Range<usize> {
+        let start = Self::HEADER_SIZE;
+        let len = usize::try_from(size).unwrap_or(usize::MAX);
+
+        start..start.saturating_add(len)
+    }
+
+    /// Reads a [`BootSpHeader`] from the start of the given buffer, checking
+    /// both its magic and its version.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, IpccDataError> {
+        let (header, _) = Self::read_from_prefix(buf).map_err(|_| {
+            IpccDataError::BootSpHeaderTooShort {
+                got: buf.len(),
+                need: core::mem::size_of::<Self>(),
+            }
+        })?;
+
+        if header.magic != Self::MAGIC {
+            return Err(IpccDataError::BootSpHeaderBadMagic(header.magic));
+        }
+
+        if header.version != Self::VERSION {
+            return Err(IpccDataError::BootSpHeaderBadVersion(header.version));
+        }
+
+        Ok(header)
+    }
+
+    /// Searches the given buffer (e.g., a raw flash dump) for a header,
+    /// returning the first valid header (that is, one with both the correct
+    /// magic and the correct version) along with its offset into the buffer.
+    pub fn find_in(buf: &[u8]) -> Option<(usize, Self)> {
+        let magic = Self::MAGIC.to_le_bytes();
+
+        buf.windows(magic.len())
+            .enumerate()
+            .filter(|(_, w)| *w == magic)
+            .find_map(|(offset, _)| {
+                Self::from_bytes(&buf[offset..]).ok().map(|h| (offset, h))
+            })
+    }
+
+    /// Checks that the sizes in the header are consistent with one another:
+    /// there must be data following the header; the image must fit in its
+    /// target; and an image that is not compressed must be exactly the data
+    /// that follows the header.
+    pub fn validate_sizes(&self) -> Result<(), IpccDataError> {
+        let fail = |constraint| {
+            Err(IpccDataError::BootSpHeaderInconsistent(constraint))
+        };
+
+        if self.data_size == 0 {
+            return fail("data size is zero");
+        }
+
+        if self.image_size == 0 {
+            return fail("image size is zero");
+        }
+
+        if self.image_size > self.target_size {
+            return fail("image size exceeds target size");
+        }
+
+        if !self.is_compressed() && self.data_size != self.image_size {
+            return fail("uncompressed image size differs from data size");
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that the given image is of the size indicated by the header,
+    /// and that its SHA-256 digest matches that of the header.
+    #[cfg(feature = "verify")]
+    pub fn verify_image(&self, image: &[u8]) -> Result<(), IpccDataError> {
+        use sha2::{Digest, Sha256};
+
+        if image.len() as u64 != self.image_size {
+            return Err(IpccDataError::ImageSizeMismatch {
+                expected: self.image_size,
+                got: image.len(),
+            });
+        }
+
+        let computed: [u8; 32] = Sha256::digest(image).into();
+
+        if computed != self.sha256 {
+            return Err(IpccDataError::ImageDigestMismatch {
+                expected: self.sha256,
+                computed,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the image contained in the given data (that is, the data that
+    /// follows the header), inflating it if the header indicates that it is
+    /// compressed.  As in `oxide_boot_sp`, compressed images are expected to
+    /// be zlib streams.  The resulting image is checked against the image
+    /// size in the header; inflation stops once the image exceeds that size,
+    /// so a corrupt (or malicious) stream can't exhaust memory.
+    #[cfg(feature = "decompress")]
+    pub fn decompress_image(
+        &self,
+        data: &[u8],
+    ) -> Result<Vec<u8>, IpccDataError> {
+        use std::io::Read;
+
+        let image = if self.is_compressed() {
+            let mut image = vec![];
+
+            //
+            // We allow one byte more than the image size so that an image
+            // that is too large is detected by the size check below.
+            //
+            flate2::read::ZlibDecoder::new(data)
+                .take(self.image_size.saturating_add(1))
+                .read_to_end(&mut image)
+                .map_err(IpccDataError::Decompress)?;
+            image
+        } else {
+            data.to_vec()
+        };
+
+        if image.len() as u64 != self.image_size {
+            return Err(IpccDataError::ImageSizeMismatch {
+                expected: self.image_size,
+                got: image.len(),
+            });
+        }
+
+        Ok(image)
+    }
+
+    /// Returns the dataset name, up to the first NUL (if any).
+    pub fn dataset_str(&self) -> Cow<'_, str> {
+        c_str_lossy(&self.dataset)
+    }
+
+    /// Returns the image name, up to the first NUL (if any).
+    pub fn imagename_str(&self) -> Cow<'_, str> {
+        c_str_lossy(&self.imagename)
+    }
+}
+
+fn c_str_lossy(buf: &[u8]) -> Cow<'_, str> {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEM_NOP: u8 = 0;
+    const ITEM_MESSAGE: u8 = 1;
+    const ITEM_STACK_ENTRY: u8 = 2;
+    const ITEM_ANCILLARY: u8 = 3;
+
+    fn push_u64s(d: &mut Vec<u8>, vals: &[u64]) {
+        for val in vals {
+            d.extend_from_slice(&val.to_le_bytes());
+        }
+    }
+
+    //
+    // A V1 payload from an explicit call to panic, with a single stack frame.
+    //
+    fn v1_call() -> Vec<u8> {
+        let mut d = vec![1];
+        d.extend_from_slice(&0xca11u16.to_le_bytes());
+        d.extend_from_slice(&0u32.to_le_bytes());
+        d.extend_from_slice(&0u32.to_le_bytes());
+        push_u64s(
+            &mut d,
+            &[
+                0xfffffe00016a2c20,
+                0,
+                0xfffffffff7a0b3f2,
+                0xfffffe0001e0fa40,
+                0,
+            ],
+        );
+
+        let mut message = [0u8; IPCC_PANIC_V1_MSGLEN];
+        let msg = b"forced crash dump initiated at user request";
+        message[..msg.len()].copy_from_slice(msg);
+        d.extend_from_slice(&message);
+
+        d.push(1);
+        let mut stack = [0u8; IPCC_PANIC_V1_STACKS * 48];
+        stack[..8].copy_from_slice(b"panicsys");
+        stack[32..40].copy_from_slice(&0xfffffffffbc3a1d0u64.to_le_bytes());
+        stack[40..48].copy_from_slice(&0x12u64.to_le_bytes());
+        d.extend_from_slice(&stack);
+
+        d.push(0);
+        d.extend_from_slice(&[0u8; IPCC_PANIC_V1_DATALEN]);
+        d
+    }
+
+    fn v2_item(ftype: u8, data: &[u8]) -> Vec<u8> {
+        let mut d = vec![ftype];
+        d.extend_from_slice(&(data.len() as u16 + 3).to_le_bytes());
+        d.extend_from_slice(data);
+        d
+    }
+
+    fn v2_stack_entry(addr: u64, offset: u64, symbol: &str) -> Vec<u8> {
+        let mut d = vec![];
+        push_u64s(&mut d, &[addr, offset]);
+        d.extend_from_slice(symbol.as_bytes());
+        v2_item(ITEM_STACK_ENTRY, &d)
+    }
+
+    //
+    // A V2 payload from a page fault, with the given items following the
+    // header.
+    //
+    fn v2_payload(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut d = vec![2];
+        d.extend_from_slice(&0xa900u16.to_le_bytes());
+        d.extend_from_slice(&2u32.to_le_bytes());
+        push_u64s(&mut d, &[1234567890, 1700000000, 500]);
+        d.extend_from_slice(&3u32.to_le_bytes());
+        push_u64s(
+            &mut d,
+            &[
+                0xfffffe0001e0fc20,
+                0,
+                0xfffffffffbc3a1d0,
+                0xfffffe0001e0fa40,
+                0xfffffe0001e0fa50,
+            ],
+        );
+
+        let mut registers = [0u64; 30];
+        registers[23] = 0xe;
+        registers[24] = 2;
+        registers[25] = 0xfffffffffbc3a1d0;
+        registers[26] = 0x30;
+        push_u64s(&mut d, &registers);
+
+        d.extend_from_slice(&(items.len() as u16).to_le_bytes());
+        let items = items.concat();
+        d.extend_from_slice(&(items.len() as u16).to_le_bytes());
+        d.extend_from_slice(&items);
+        d
+    }
+
+    const V2_TRAP_MESSAGE: &str =
+        "BAD TRAP: type=e (#pf Page fault) rp=fffffe0001e0fa50 addr=0";
+
+    //
+    // The V2 payload from a page fault, with a message, a two frame stack and
+    // some ancillary data.
+    //
+    fn v2_trap() -> Vec<u8> {
+        v2_payload(&[
+            v2_item(ITEM_MESSAGE, V2_TRAP_MESSAGE.as_bytes()),
+            v2_stack_entry(0xfffffffffbc3a1d0, 0x43, "die"),
+            v2_stack_entry(0xfffffffffbc3b000, 0x21, "trap"),
+            v2_item(ITEM_ANCILLARY, &[1, 2, 3, 4]),
+        ])
+    }
+
+    //
+    // The same V2 payload, as it is received when suffering from hubris#1554:
+    // without its first two bytes.
+    //
+    fn v2_hubris1554() -> Vec<u8> {
+        v2_trap()[2..].to_vec()
+    }
+
+    fn decode(d: &[u8]) -> PanicData {
+        PanicData::from_slice(d).unwrap().unwrap()
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        for d in [v1_call(), v2_trap()] {
+            let data = decode(&d);
+            let json = serde_json::to_string(&data).unwrap();
+            let rval: PanicData = serde_json::from_str(&json).unwrap();
+            assert_eq!(format!("{:?}", rval), format!("{:?}", data));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_registers_by_name() {
+        let json = serde_json::to_value(decode(&v2_trap())).unwrap();
+
+        assert_eq!(json["version"], serde_json::json!({ "Determined": 2 }));
+        assert_eq!(json["registers"]["trapno"], 0xe);
+        assert_eq!(json["registers"]["rip"], 0xfffffffffbc3a1d0u64);
+    }
+
+    fn bootfail_ramdisk() -> Vec<u8> {
+        let mut d = vec![0x05];
+        d.extend_from_slice(b"failed to mount ramdisk");
+        d.resize(IPCC_BOOTFAIL_MAX_PAYLOAD, 0);
+        d
+    }
+
+    #[test]
+    fn bootfail() {
+        let b = BootFailData::from_bytes(bootfail_ramdisk())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(b.cause, BootFailCause::Ramdisk);
+        assert_eq!(b.cause.to_string(), "IPCC_BOOTFAIL_RAMDISK");
+        assert_eq!(b.message.as_deref(), Some("failed to mount ramdisk"));
+    }
+
+    #[test]
+    fn bootfail_without_message() {
+        assert!(BootFailData::from_bytes(vec![]).unwrap().is_none());
+        assert!(BootFailData::from_bytes(vec![0; 16]).unwrap().is_none());
+
+        let b = BootFailData::from_bytes(vec![0x2a]).unwrap().unwrap();
+        assert_eq!(b.cause, BootFailCause::Unknown(0x2a));
+        assert_eq!(b.message, None);
+    }
+
+    #[test]
+    fn bootfail_too_long() {
+        let mut d = bootfail_ramdisk();
+        d.push(0);
+
+        assert!(matches!(
+            BootFailData::from_bytes(d),
+            Err(IpccDataError::BootFailTooLong {
+                got: 0x101,
+                max: 0x100
+            })
+        ));
+    }
+
+    #[test]
+    fn registers_in_dumpregs_order() {
+        let dumpregs = [
+            "rdi", "rsi", "rdx", "rcx", "r8", "r9", "rax", "rbx", "rbp", "r10",
+            "r11", "r12", "r13", "r14", "r15", "fsbase", "gsbase", "ds", "es",
+            "fs", "gs", "trapno", "err", "rip", "cs", "rfl", "rsp", "ss",
+        ];
+
+        let registers = decode(&v2_trap())
+            .registers
+            .unwrap()
+            .keys()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(registers, dumpregs);
+
+        let all = Register::all()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(all, dumpregs);
+    }
+
+    #[test]
+    fn short_payloads() {
+        assert!(PanicData::from_slice(&[]).unwrap().is_none());
+        assert!(PanicData::from_bytes(vec![]).unwrap().is_none());
+
+        assert!(matches!(
+            PanicData::from_slice(&[2]),
+            Err(IpccDataError::PayloadTooShort { got: 1, .. })
+        ));
+
+        let d = v2_trap();
+
+        assert!(matches!(
+            PanicData::from_slice(&d[..100]),
+            Err(IpccDataError::PayloadTooShort { got: 100, .. })
+        ));
+
+        assert!(matches!(
+            PanicData::from_slice(&d[..d.len() - 1]),
+            Err(IpccDataError::Deserialize(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_messages() {
+        let first = v2_item(ITEM_MESSAGE, b"first");
+        let second = v2_item(ITEM_MESSAGE, b"second");
+
+        let data = decode(&v2_payload(&[first.clone(), second.clone()]));
+        assert_eq!(data.message.as_deref(), Some("first\nsecond"));
+
+        let data = decode(&v2_payload(&[second, first]));
+        assert_eq!(data.message.as_deref(), Some("second\nfirst"));
+    }
+
+    #[test]
+    fn ancillary_items() {
+        assert_eq!(decode(&v2_trap()).ancillary, [vec![1, 2, 3, 4]]);
+
+        let data = decode(&v2_payload(&[
+            v2_item(ITEM_ANCILLARY, b"first"),
+            v2_item(ITEM_NOP, &[]),
+            v2_item(0x7f, b"unknown"),
+            v2_item(ITEM_ANCILLARY, b"second"),
+        ]));
+
+        assert_eq!(data.ancillary, [b"first".to_vec(), b"second".to_vec()]);
+        assert_eq!(data.message, None);
+        assert!(data.stack.is_empty());
+    }
+
+    #[test]
+    fn v1_ancillary() {
+        let data = IPCC_PANIC_V1_LEN - IPCC_PANIC_V1_DATALEN;
+        let mut d = v1_call();
+        d[data - 1] = 4;
+        d[data..][..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+
+        assert_eq!(decode(&d).ancillary, [vec![0xde, 0xad, 0xbe, 0xef]]);
+
+        d[data - 1] = 0xff;
+        assert_eq!(decode(&d).ancillary[0].len(), 0xff);
+
+        d[data - 1] = 0;
+        assert!(decode(&d).ancillary.is_empty());
+    }
+
+    #[test]
+    fn display() {
+        let expected = concat!(
+            "    version: 1 (determined)\n",
+            "      cause: IPCC_PANIC_CALL\n",
+            "      error: 0x0\n",
+            "      cpuid: 0\n",
+            "     thread: 0xfffffe00016a2c20\n",
+            "       addr: 0x0\n",
+            "         pc: 0xfffffffff7a0b3f2\n",
+            "         fp: 0xfffffe0001e0fa40\n",
+            "         rp: 0x0\n",
+            "    message: forced crash dump initiated at user request\n",
+            "\n",
+            "    panicsys+0x12 0xfffffffffbc3a1d0\n",
+        );
+
+        assert_eq!(decode(&v1_call()).to_string(), expected);
+    }
+
+    #[test]
+    fn from_slice_matches_from_bytes() {
+        for d in [v1_call(), v2_trap()] {
+            assert_eq!(
+                format!("{:?}", PanicData::from_slice(&d).unwrap()),
+                format!("{:?}", PanicData::from_bytes(d).unwrap())
+            );
+        }
+    }
+
+    fn bootsp_header(dataset: &str, imagename: &str) -> BootSpHeader {
+        let mut header = BootSpHeader {
+            magic: BootSpHeader::MAGIC,
+            version: BootSpHeader::VERSION,
+            flags: 0,
+            data_size: 0,
+            image_size: 0,
+            target_size: 0,
+            sha256: [0; 32],
+            dataset: [0; 128],
+            imagename: [0; 128],
+        };
+
+        header.dataset[..dataset.len()].copy_from_slice(dataset.as_bytes());
+        header.imagename[..imagename.len()]
+            .copy_from_slice(imagename.as_bytes());
+        header
+    }
+
+    #[test]
+    fn bootsp_header_from_bytes() {
+        let header = bootsp_header("rpool/ROOT/ramdisk", "phase2");
+        let buf = zerocopy::IntoBytes::as_bytes(&header).to_vec();
+
+        let header = BootSpHeader::from_bytes(&buf).unwrap();
+        assert_eq!(header.magic, BootSpHeader::MAGIC);
+        assert_eq!(header.version, BootSpHeader::VERSION);
+
+        let mut bad = buf.clone();
+        bad[0] ^= 0xff;
+        assert!(matches!(
+            BootSpHeader::from_bytes(&bad),
+            Err(IpccDataError::BootSpHeaderBadMagic(_))
+        ));
+
+        let mut bad = buf.clone();
+        bad[4..8].copy_from_slice(&1u32.to_ne_bytes());
+        assert!(matches!(
+            BootSpHeader::from_bytes(&bad),
+            Err(IpccDataError::BootSpHeaderBadVersion(1))
+        ));
+
+        assert!(matches!(
+            BootSpHeader::from_bytes(&buf[..16]),
+            Err(IpccDataError::BootSpHeaderTooShort { got: 16, .. })
+        ));
+    }
+
+    #[test]
+    fn bootsp_header_strings() {
+        let mut header = bootsp_header("rpool", "phase2");
+        assert_eq!(header.dataset_str(), "rpool");
+        assert_eq!(header.imagename_str(), "phase2");
+
+        //
+        // A field that is entirely populated has no terminating NUL.
+        //
+        header.dataset = [b'x'; 128];
+        assert_eq!(header.dataset_str(), "x".repeat(128));
+
+        header.imagename[0] = 0xff;
+        assert_eq!(header.imagename_str(), "\u{fffd}hase2");
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn verify_image() {
+        let mut header = bootsp_header("rpool", "phase2");
+        header.image_size = 3;
+
+        //
+        // This is the SHA-256 digest of "abc".
+        //
+        header.sha256 = [
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40,
+            0xde, 0x5d, 0xae, 0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17,
+            0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+        ];
+
+        header.verify_image(b"abc").unwrap();
+
+        assert!(matches!(
+            header.verify_image(b"abd"),
+            Err(IpccDataError::ImageDigestMismatch { .. })
+        ));
+
+        assert!(matches!(
+            header.verify_image(b"abcd"),
+            Err(IpccDataError::ImageSizeMismatch {
+                expected: 3,
+                got: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn bootsp_flags() {
+        let mut header = bootsp_header("rpool", "phase2");
+        assert!(!header.is_compressed());
+        assert!(header.flags().is_empty());
+
+        let unknown = 0x8000_0000_0000_0100u64;
+        header.flags = u64::from(BootSpHeader::FLAG_COMPRESSED) | unknown;
+
+        let flags = header.flags();
+        assert!(header.is_compressed());
+        assert!(flags.contains(BootSpFlags::COMPRESSED));
+        assert_eq!(flags.bits(), header.flags);
+        assert_eq!((flags - BootSpFlags::COMPRESSED).bits(), unknown);
+    }
+
+    #[cfg(feature = "decompress")]
+    #[test]
+    fn decompress_image() {
+        use std::io::Write;
+
+        let image = b"a boot image, as it might be compressed ".repeat(16);
+
+        let mut encoder = flate2::write::ZlibEncoder::new(
+            vec![],
+            flate2::Compression::default(),
+        );
+        encoder.write_all(&image).unwrap();
+        let data = encoder.finish().unwrap();
+
+        let mut header = bootsp_header("rpool", "phase2");
+        header.flags = u64::from(BootSpHeader::FLAG_COMPRESSED);
+        header.data_size = data.len() as u64;
+        header.image_size = image.len() as u64;
+        assert_eq!(header.decompress_image(&data).unwrap(), image);
+
+        //
+        // An image that inflates beyond its size is caught as soon as it
+        // exceeds that size.
+        //
+        header.image_size = 16;
+        assert!(matches!(
+            header.decompress_image(&data),
+            Err(IpccDataError::ImageSizeMismatch {
+                expected: 16,
+                got: 17
+            })
+        ));
+
+        assert!(matches!(
+            header.decompress_image(&data[..data.len() / 2]),
+            Err(IpccDataError::Decompress(_))
+                | Err(IpccDataError::ImageSizeMismatch { .. })
+        ));
+
+        //
+        // An image that isn't compressed is returned as is.
+        //
+        header.flags = 0;
+        header.image_size = image.len() as u64;
+        assert_eq!(header.decompress_image(&image).unwrap(), image);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_reader() {
+        for d in [v1_call(), v2_trap()] {
+            let mut cursor = std::io::Cursor::new(&d);
+
+            assert_eq!(
+                format!("{:?}", PanicData::from_reader(&mut cursor).unwrap()),
+                format!("{:?}", PanicData::from_slice(&d).unwrap())
+            );
+        }
+
+        let mut empty = std::io::empty();
+        assert!(PanicData::from_reader(&mut empty).unwrap().is_none());
+    }
+
+    #[test]
+    fn cause_inferred() {
+        let data = decode(&v1_call());
+        assert_eq!(data.cause, PanicCause::Call);
+        assert!(!data.cause_inferred);
+
+        //
+        // Without its first two bytes, the low byte of the cause has to be
+        // reconstructed from the high byte.
+        //
+        let data = decode(&v1_call()[2..]);
+        assert_eq!(data.cause, PanicCause::Call);
+        assert!(data.cause_inferred);
+        assert_eq!(data.raw_cause, 0xca11);
+
+        let data = decode(&v2_trap());
+        assert_eq!(data.raw_cause, 0xa900);
+
+        let data = decode(&v2_hubris1554());
+        assert_eq!(data.cause, PanicCause::Trap);
+        assert!(data.cause_inferred);
+        assert_eq!(data.raw_cause, 0xa900);
+    }
+
+    #[test]
+    fn page_fault_info() {
+        let mut data = decode(&v2_trap());
+        assert_eq!(data.page_fault_info(), Some(PageFaultFlags::WRITE));
+
+        for (err, flags) in [
+            (0x0, PageFaultFlags::empty()),
+            (0x5, PageFaultFlags::PRESENT | PageFaultFlags::USER),
+            (0x11, PageFaultFlags::PRESENT | PageFaultFlags::INSTRUCTION),
+            (0x8, PageFaultFlags::RESERVED),
+            (0x8000, PageFaultFlags::SGX),
+        ] {
+            data.registers.as_mut().unwrap().insert(Register::err, err);
+            assert_eq!(data.page_fault_info(), Some(flags), "{err:#x}");
+        }
+
+        //
+        // Only a page fault has such an error code.
+        //
+        data.registers
+            .as_mut()
+            .unwrap()
+            .insert(Register::trapno, 0xd);
+        assert_eq!(data.page_fault_info(), None);
+
+        assert_eq!(decode(&v1_call()).page_fault_info(), None);
+    }
+
+    #[test]
+    fn trap() {
+        for (trapno, trap, display) in [
+            (0x0u64, Trap::DivideError, "#DE (divide error)"),
+            (0x3, Trap::Breakpoint, "#BP (breakpoint)"),
+            (0x6, Trap::InvalidOpcode, "#UD (invalid opcode)"),
+            (0x8, Trap::DoubleFault, "#DF (double fault)"),
+            (0xd, Trap::GeneralProtection, "#GP (general protection)"),
+            (0xe, Trap::PageFault, "#PF (page fault)"),
+            (0x12, Trap::MachineCheck, "#MC (machine check)"),
+            (0x42, Trap::Unknown(0x42), "<Unknown trap 0x42>"),
+        ] {
+            assert_eq!(Trap::from(trapno), trap);
+            assert_eq!(trap.to_string(), display);
+        }
+
+        assert_eq!(decode(&v2_trap()).trap(), Some(Trap::PageFault));
+        assert_eq!(decode(&v1_call()).trap(), None);
+    }
+
+    #[test]
+    fn stack_signature() {
+        let data = decode(&v2_trap());
+
+        //
+        // The signature considers only the cause and the stack's symbols...
+        //
+        let mut other = decode(&v2_trap());
+        other.pc = Addr(0);
+        other.stack[0].offset = 0x44;
+        other.stack[1].address = Addr(0);
+        assert_eq!(data.stack_signature(), other.stack_signature());
+
+        other.stack.reverse();
+        assert_ne!(data.stack_signature(), other.stack_signature());
+
+        let mut other = decode(&v2_trap());
+        other.raw_cause = 0x5e00;
+        assert_ne!(data.stack_signature(), other.stack_signature());
+
+        //
+        // ...and is stable across runs, machines and versions of this crate.
+        //
+        assert_eq!(data.stack_signature(), 0x1779_457d_2076_9050);
+        assert_eq!(
+            decode(&v2_hubris1554()).stack_signature(),
+            0x1779_457d_2076_9050
+        );
+    }
+
+    #[test]
+    fn symbolize() {
+        let mut data = decode(&v2_trap());
+        data.stack = vec![
+            StackFrame::raw(Addr(0x1000)),
+            StackFrame::symbolic("known", 0x8),
+            StackFrame::raw(Addr(0x2000)),
+        ];
+
+        let resolve = |addr: Addr| match addr.0 {
+            0x1000 => Some(("resolved".to_string(), 0x10)),
+            0 => Some(("forced".to_string(), 0x20)),
+            _ => None,
+        };
+
+        let frames = |data: &PanicData| {
+            data.stack
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        data.symbolize(resolve, false);
+        assert_eq!(frames(&data), ["resolved+0x10", "known+0x8", "0x2000"]);
+
+        data.symbolize(resolve, true);
+        assert_eq!(frames(&data), ["resolved+0x10", "forced+0x20", "0x2000"]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn system_time() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        let time = |sec, nsec| AdjustedTime { sec, nsec };
+
+        assert_eq!(SystemTime::try_from(&time(0, 0)).unwrap(), UNIX_EPOCH);
+
+        assert_eq!(
+            SystemTime::try_from(&time(1_700_000_000, 500)).unwrap(),
+            UNIX_EPOCH + Duration::new(1_700_000_000, 500)
+        );
+
+        assert!(matches!(
+            SystemTime::try_from(&time(0, 1_000_000_000)),
+            Err(IpccDataError::IllegalNsec(1_000_000_000))
+        ));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn to_datetime() {
+        let time = |sec, nsec| AdjustedTime { sec, nsec };
+
+        assert_eq!(time(0, 0).to_datetime().unwrap().timestamp(), 0);
+
+        let datetime = time(1_700_000_000, 500).to_datetime().unwrap();
+        assert_eq!(datetime.timestamp(), 1_700_000_000);
+        assert_eq!(datetime.timestamp_subsec_nanos(), 500);
+
+        assert!(matches!(
+            time(0, 1_000_000_000).to_datetime(),
+            Err(IpccDataError::IllegalNsec(1_000_000_000))
+        ));
+
+        assert!(matches!(
+            time(u64::MAX, 0).to_datetime(),
+            Err(IpccDataError::TimeOutOfRange { .. })
+        ));
+    }
+
+    #[test]
+    fn suspect_times() {
+        let time = |sec| AdjustedTime { sec, nsec: 0 };
+
+        assert!(time(0).is_suspect());
+        assert!(time(489_024_000).is_suspect());
+        assert!(time(AdjustedTime::SUSPECT_BEFORE - 1).is_suspect());
+        assert!(!time(AdjustedTime::SUSPECT_BEFORE).is_suspect());
+        assert!(!time(1_700_000_000).is_suspect());
+
+        assert!(time(1_700_000_000).is_suspect_before(u64::MAX));
+        assert!(!time(1_700_000_000).is_suspect_before(0));
+    }
+
+    #[test]
+    fn register_from_str() {
+        for &reg in Register::all() {
+            assert_eq!(reg.to_string().parse::<Register>().unwrap(), reg);
+        }
+
+        assert!(matches!(
+            "xmm0".parse::<Register>(),
+            Err(IpccDataError::UnknownRegister(s)) if s == "xmm0"
+        ));
+    }
+
+    #[test]
+    fn panic_cause_from_str() {
+        for cause in [
+            PanicCause::Call,
+            PanicCause::Trap,
+            PanicCause::UserTrap,
+            PanicCause::EarlyBoot,
+            PanicCause::EarlyBootPROM,
+            PanicCause::EarlyBootTrap,
+            PanicCause::EarlyBootUnknown,
+            PanicCause::EarlyBootOther(0x42),
+        ] {
+            assert_eq!(cause.to_string().parse::<PanicCause>().unwrap(), cause);
+        }
+
+        for (s, cause) in [
+            ("0xca11", PanicCause::Call),
+            ("0xeb42", PanicCause::EarlyBootOther(0x42)),
+            ("<Unknown early boot cause 0x97>", PanicCause::EarlyBootPROM),
+            ("0x1234", PanicCause::Unknown(0x1234)),
+        ] {
+            assert_eq!(s.parse::<PanicCause>().unwrap(), cause);
+        }
+
+        for s in [
+            "IPCC_PANIC_BOGUS",
+            "0x",
+            "0x12345",
+            "ca11",
+            "<Unknown early boot cause 0x142>",
+            "<Unknown early boot cause 0x42",
+        ] {
+            assert!(matches!(
+                s.parse::<PanicCause>(),
+                Err(IpccDataError::UnknownPanicCause(_))
+            ));
+        }
+    }
+
+    const V1_MESSAGE: usize = 51;
+
+    #[test]
+    fn v1_invalid_message() {
+        let mut d = v1_call();
+        d[V1_MESSAGE] = 0xff;
+
+        let data = decode(&d);
+        assert_eq!(
+            data.message.as_deref(),
+            Some("\u{fffd}orced crash dump initiated at user request")
+        );
+        assert_eq!(data.stack[0].symbol.as_deref(), Some("panicsys"));
+    }
+
+    #[test]
+    fn options() {
+        let mut d = v1_call();
+        d[V1_MESSAGE] = 0xff;
+
+        let opts = PanicDataOptions::new().lossy_strings(false);
+        assert!(matches!(
+            PanicData::from_slice_with(&d, &opts),
+            Err(IpccDataError::InvalidMessage(_))
+        ));
+
+        let opts = PanicDataOptions::new().require_determined_version(true);
+        assert!(PanicData::from_slice_with(&v1_call(), &opts).is_ok());
+        assert!(matches!(
+            PanicData::from_slice_with(&v1_call()[2..], &opts),
+            Err(IpccDataError::VersionNotDetermined(1))
+        ));
+
+        let opts = PanicDataOptions::new().max_stack_frames(1);
+        let data = PanicData::from_slice_with(&v2_trap(), &opts)
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.stack.len(), 1);
+        assert_eq!(data.stack[0].symbol.as_deref(), Some("die"));
+    }
+
+    const V1_STACKIDX: usize = V1_MESSAGE + IPCC_PANIC_V1_MSGLEN;
+
+    #[test]
+    fn v1_stackidx_clamped() {
+        assert!(!decode(&v1_call()).stack_clamped);
+
+        let mut d = v1_call();
+        d[V1_STACKIDX] = 200;
+
+        let data = decode(&d);
+        assert!(data.stack_clamped);
+        assert_eq!(data.stack.len(), IPCC_PANIC_V1_STACKS);
+        assert_eq!(data.stack[0].symbol.as_deref(), Some("panicsys"));
+
+        //
+        // An index that covers the entire stack is not out of bounds.
+        //
+        d[V1_STACKIDX] = IPCC_PANIC_V1_STACKS as u8;
+        assert!(!decode(&d).stack_clamped);
+    }
+
+    #[test]
+    fn from_hex() {
+        let expected = decode(&v1_call());
+        let hex = v1_call()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>();
+        let prefixed = hex.iter().map(|b| format!("0x{b}")).collect::<Vec<_>>();
+
+        for s in [
+            hex.join(" "),
+            hex.join("\n"),
+            hex.join(", "),
+            prefixed.join(","),
+            hex.concat(),
+        ] {
+            assert_eq!(
+                format!("{:?}", PanicData::from_hex(&s).unwrap().unwrap()),
+                format!("{:?}", expected)
+            );
+        }
+
+        assert!(PanicData::from_hex("").unwrap().is_none());
+        assert!(PanicData::from_hex("0 0 00 0x00").unwrap().is_none());
+
+        for (s, token) in [("01 zz", "zz"), ("01 0x", "0x"), ("01 012", "012")]
+        {
+            assert!(matches!(
+                PanicData::from_hex(s),
+                Err(IpccDataError::InvalidHex(t)) if t == token
+            ));
+        }
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn from_base64() {
+        use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+        use base64::Engine;
+
+        let d = v2_trap();
+        let expected = decode(&d);
+
+        for s in [
+            STANDARD.encode(&d),
+            URL_SAFE.encode(&d),
+            format!("  {}\n", STANDARD.encode(&d)),
+        ] {
+            assert_eq!(
+                format!("{:?}", PanicData::from_base64(&s).unwrap().unwrap()),
+                format!("{:?}", expected)
+            );
+        }
+
+        assert!(matches!(
+            PanicData::from_base64("not base64!"),
+            Err(IpccDataError::InvalidBase64(_))
+        ));
+    }
+
+    #[test]
+    fn payload_lengths() {
+        assert_eq!(v1_call().len(), IPCC_PANIC_V1_LEN);
+        assert_eq!(v2_payload(&[]).len(), IPCC_PANIC_V2_HEADER_LEN);
+    }
+
+    #[test]
+    fn version_accessors() {
+        let data = decode(&v2_trap());
+        assert_eq!(data.version_number(), 2);
+        assert!(!data.version_was_inferred());
+        assert!(!data.cause_inferred);
+
+        let data = decode(&v1_call()[2..]);
+        assert_eq!(data.version_number(), 1);
+        assert!(data.version_was_inferred());
+        assert!(data.cause_inferred);
+        assert_eq!(data.version.to_string(), "1 (inferred)");
+    }
+
+    #[test]
+    fn from_bytes_with_raw() {
+        let d = v2_trap();
+        let raw = PanicData::from_bytes_with_raw(d.clone()).unwrap().unwrap();
+        assert_eq!(raw.fixed, d);
+        assert_eq!(format!("{:?}", raw.data), format!("{:?}", decode(&d)));
+
+        //
+        // For a payload that suffers from hubris#1554, the bytes are those
+        // that were reconstructed.
+        //
+        let d = v1_call()[2..].to_vec();
+        let raw = PanicData::from_bytes_with_raw(d.clone()).unwrap().unwrap();
+        assert_eq!(raw.fixed[..2], [1, 0x11]);
+        assert_eq!(raw.fixed[2..], *d);
+        assert_eq!(format!("{:?}", raw.data), format!("{:?}", decode(&d)));
+    }
+
+    #[test]
+    fn best_effort() {
+        let mut d = v2_trap();
+        d[0] = 3;
+
+        assert!(matches!(
+            PanicData::from_slice(&d),
+            Err(IpccDataError::UnsupportedVersion(3))
+        ));
+
+        let data = PanicData::from_bytes_best_effort(d).unwrap().unwrap();
+        assert!(data.degraded);
+        assert_eq!(data.version_number(), 3);
+        assert_eq!(data.cause, PanicCause::Trap);
+        assert_eq!(data.error_code, 2);
+        assert_eq!(data.message, None);
+        assert!(data.stack.is_empty());
+
+        assert!(
+            !PanicData::from_bytes_best_effort(v2_trap())
+                .unwrap()
+                .unwrap()
+                .degraded
+        );
+    }
+
+    #[test]
+    fn panic_decoders() {
+        let opts = PanicDataOptions::default();
+
+        for (version, d) in [(1, v1_call()), (2, v2_trap())] {
+            let decoder = panic_decoder(version).unwrap();
+            let (data, consumed) =
+                decoder(PanicDataVersion::Determined(version), &d, &opts)
+                    .unwrap();
+
+            assert_eq!(format!("{:?}", data), format!("{:?}", decode(&d)));
+            assert_eq!(consumed, d.len());
+        }
+
+        let v2 = panic_decoder(2).unwrap();
+        assert!(v2(PanicDataVersion::Determined(2), &v1_call(), &opts).is_err());
+
+        assert!(panic_decoder(0).is_none());
+        assert!(panic_decoder(3).is_none());
+    }
+
+    #[test]
+    fn diff() {
+        let data = decode(&v2_trap());
+
+        let diff = data.diff(&decode(&v2_trap()));
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no differences\n");
+
+        let mut other = decode(&v2_trap());
+        other.cpuid = Cpuid(5);
+        other
+            .registers
+            .as_mut()
+            .unwrap()
+            .insert(Register::rip, 0xfffffffffbc3a1d4);
+        other.stack[1] = StackFrame::symbolic("cmntrap", 0x9);
+
+        let diff = data.diff(&other);
+        assert_eq!(diff.cause, None);
+        assert_eq!(diff.cpuid, Some((Cpuid(3), Cpuid(5))));
+        assert_eq!(diff.registers.len(), 1);
+        assert_eq!(diff.registers[0].register, Register::rip);
+        assert_eq!(diff.registers[0].old, Some(0xfffffffffbc3a1d0));
+        assert_eq!(diff.registers[0].new, Some(0xfffffffffbc3a1d4));
+        assert_eq!(diff.stack_divergence, Some(1));
+
+        assert_eq!(
+            diff.to_string(),
+            concat!(
+                "      cpuid: 3 -> 5\n",
+                "       %rip: fffffffffbc3a1d0 -> fffffffffbc3a1d4\n",
+                "      stack: diverges at frame 1\n",
+            )
+        );
+
+        //
+        // Registers that are absent on one side differ from any value,
+        // and a stack that is a prefix of another diverges at its end.
+        //
+        let diff = decode(&v1_call()).diff(&data);
+        assert_eq!(diff.cause, Some((PanicCause::Call, PanicCause::Trap)));
+        assert_eq!(diff.registers.len(), Register::all().len());
+        assert!(diff.registers.iter().all(|r| r.old.is_none()));
+        assert_eq!(diff.stack_divergence, Some(0));
+
+        let mut other = decode(&v2_trap());
+        other.stack.pop();
+        assert_eq!(data.diff(&other).stack_divergence, Some(1));
+    }
+
+    #[test]
+    fn faulting_address_and_mode() {
+        let mut data = decode(&v2_trap());
+        assert_eq!(data.faulting_address().map(|a| a.0), Some(0));
+        assert_eq!(data.is_user_mode(), Some(false));
+
+        data.registers.as_mut().unwrap().insert(Register::cs, 0x53);
+        assert_eq!(data.is_user_mode(), Some(true));
+
+        let data = decode(&v1_call());
+        assert!(data.faulting_address().is_none());
+        assert_eq!(data.is_user_mode(), None);
+    }
+
+    #[test]
+    fn call_registers() {
+        let mut d = v2_trap();
+        d[1..3].copy_from_slice(&PanicCause::Call.wire_value().to_le_bytes());
+
+        let data = decode(&d);
+        assert_eq!(data.cause, PanicCause::Call);
+        assert!(data.registers.is_none());
+
+        let opts = PanicDataOptions::new().call_registers(true);
+        let data = PanicData::from_slice_with(&d, &opts).unwrap().unwrap();
+        assert_eq!(
+            data.registers.unwrap().get(&Register::rip),
+            Some(&0xfffffffffbc3a1d0)
+        );
+    }
+
+    #[test]
+    fn dwarf_numbers() {
+        for (reg, number) in [
+            (Register::rax, Some(0)),
+            (Register::rdx, Some(1)),
+            (Register::rcx, Some(2)),
+            (Register::rbx, Some(3)),
+            (Register::rsi, Some(4)),
+            (Register::rdi, Some(5)),
+            (Register::rbp, Some(6)),
+            (Register::rsp, Some(7)),
+            (Register::r8, Some(8)),
+            (Register::r15, Some(15)),
+            (Register::rip, Some(16)),
+            (Register::rfl, Some(49)),
+            (Register::es, Some(50)),
+            (Register::cs, Some(51)),
+            (Register::ss, Some(52)),
+            (Register::ds, Some(53)),
+            (Register::fs, Some(54)),
+            (Register::gs, Some(55)),
+            (Register::fsbase, Some(58)),
+            (Register::gsbase, Some(59)),
+            (Register::trapno, None),
+            (Register::err, None),
+        ] {
+            assert_eq!(reg.dwarf_number(), number, "{reg}");
+        }
+    }
+
+    #[test]
+    fn collapsed_stack() {
+        let mut data = decode(&v2_trap());
+        data.stack = vec![StackFrame::symbolic("die", 0x43)];
+        data.stack
+            .extend((0..10).map(|_| StackFrame::symbolic("recurse", 0x1c)));
+        data.stack.push(StackFrame::raw(Addr(0x1000)));
+        data.stack.push(StackFrame::raw(Addr(0x1000)));
+        data.stack.push(StackFrame::symbolic("trap", 0x21));
+
+        let collapsed = data
+            .collapsed_stack()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            collapsed,
+            ["die+0x43", "recurse+0x1c (x10)", "0x1000 (x2)", "trap+0x21"]
+        );
+    }
+
+    #[test]
+    fn v2_item_too_short() {
+        for len in 0..3u16 {
+            let mut item = vec![ITEM_MESSAGE];
+            item.extend_from_slice(&len.to_le_bytes());
+
+            assert!(matches!(
+                PanicData::from_slice(&v2_payload(&[item])),
+                Err(IpccDataError::InvalidItemLength { index: 0, len: l })
+                    if l == len
+            ));
+        }
+
+        //
+        // An item that is merely empty, on the other hand, is valid.
+        //
+        let data = decode(&v2_payload(&[v2_item(ITEM_MESSAGE, &[])]));
+        assert_eq!(data.message.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn nop_items() {
+        let message = v2_item(ITEM_MESSAGE, b"message");
+        let stack = v2_stack_entry(0xfffffffffbc3a1d0, 0x43, "die");
+        let nop = v2_item(ITEM_NOP, &[]);
+
+        let data = decode(&v2_payload(&[message.clone(), stack.clone()]));
+        let padded = decode(&v2_payload(&[
+            nop.clone(),
+            message,
+            nop.clone(),
+            stack,
+            nop,
+        ]));
+
+        assert_eq!(format!("{:?}", padded), format!("{:?}", data));
+        assert!(!padded.nop_data);
+
+        //
+        // A Nop item that carries data is skipped all the same, but noted.
+        //
+        let data = decode(&v2_payload(&[v2_item(ITEM_NOP, b"junk")]));
+        assert!(data.nop_data);
+        assert!(data.ancillary.is_empty());
+    }
+
+    #[test]
+    fn rflags() {
+        let flags = RFlags::from_bits_retain(0x246);
+        assert!(flags.contains(RFlags::PF | RFlags::ZF | RFlags::IF));
+        assert!(!flags.intersects(RFlags::CF | RFlags::SF | RFlags::OF));
+        assert_eq!(flags.iopl(), 0);
+        assert_eq!(flags.to_string(), "<if,zf,pf>");
+
+        let flags = RFlags::from_bits_retain(0x3a93);
+        assert_eq!(flags.iopl(), 3);
+        assert_eq!(flags.to_string(), "<of,if,sf,af,cf>");
+
+        assert_eq!(RFlags::empty().to_string(), "<>");
+
+        assert_eq!(decode(&v2_trap()).rflags(), Some(RFlags::empty()));
+        assert_eq!(decode(&v1_call()).rflags(), None);
+    }
+
+    #[test]
+    fn privilege_level() {
+        let mut data = decode(&v2_trap());
+        assert_eq!(data.privilege_level(), Some(0));
+
+        data.registers.as_mut().unwrap().insert(Register::cs, 0x53);
+        assert_eq!(data.privilege_level(), Some(3));
+
+        assert_eq!(decode(&v1_call()).privilege_level(), None);
+    }
+
+    #[test]
+    fn v2_round_trip() {
+        //
+        // Decoding and re-encoding V2 panic data reproduces it exactly.
+        //
+        let data = decode(&v2_trap());
+        let d = data.to_v2_bytes().unwrap();
+        assert_eq!(d, v2_trap());
+        assert_eq!(format!("{:?}", decode(&d)), format!("{:?}", data));
+
+        //
+        // V1 panic data can be encoded as V2; its (absent) times are written
+        // as zero, and so remain absent, while its (absent) registers come
+        // back as zero raw registers.
+        //
+        let mut data = decode(&v1_call());
+        let v2 = decode(&data.to_v2_bytes().unwrap());
+        data.version = PanicDataVersion::Determined(2);
+        data.raw_registers = Some(RawRegisters::default());
+        assert_eq!(format!("{:?}", v2), format!("{:?}", data));
+
+        //
+        // Panic data affected by hubris#1554 keeps its reconstructed cause,
+        // but no longer records that it was inferred.
+        //
+        let mut data = decode(&v1_call()[2..]);
+        assert!(data.cause_inferred);
+        let v2 = decode(&data.to_v2_bytes().unwrap());
+        data.version = PanicDataVersion::Determined(2);
+        data.cause_inferred = false;
+        data.raw_registers = Some(RawRegisters::default());
+        assert_eq!(format!("{:?}", v2), format!("{:?}", data));
+
+        let mut data = decode(&v2_trap());
+        data.ancillary = vec![vec![0; usize::from(u16::MAX)]];
+        assert!(matches!(
+            data.to_v2_bytes(),
+            Err(IpccDataError::SerializeOverflow { field: "item", .. })
+        ));
+    }
+
+    #[test]
+    fn bootsp_header_round_trip() {
+        let mut header =
+            BootSpHeader::new("rpool/ROOT/ramdisk", "phase2").unwrap();
+        header.data_size = 0x2000;
+        header.image_size = 0x2000;
+        header.target_size = 0x4000;
+
+        let buf = header.to_bytes();
+        assert_eq!(buf.len(), BootSpHeader::HEADER_SIZE);
+
+        let rval = BootSpHeader::from_bytes(&buf).unwrap();
+        assert_eq!(rval.dataset_str(), "rpool/ROOT/ramdisk");
+        assert_eq!(rval.imagename_str(), "phase2");
+        assert_eq!(rval.data_size, 0x2000);
+        assert_eq!(rval.image_size, 0x2000);
+        assert_eq!(rval.target_size, 0x4000);
+
+        let long = "x".repeat(129);
+        assert!(BootSpHeader::new(&long[..128], "phase2").is_ok());
+
+        assert!(matches!(
+            BootSpHeader::new(&long, "phase2"),
+            Err(IpccDataError::BootSpHeaderFieldTooLong {
+                field: "dataset",
+                got: 129,
+                max: 128
+            })
+        ));
+
+        assert!(matches!(
+            BootSpHeader::new("rpool", &long),
+            Err(IpccDataError::BootSpHeaderFieldTooLong {
+                field: "imagename",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn bootsp_header_sizes() {
+        let mut header = bootsp_header("rpool", "phase2");
+        header.data_size = 0x1000;
+        header.image_size = 0x1000;
+        header.target_size = 0x2000;
+        header.validate_sizes().unwrap();
+
+        let inconsistent = |header: &BootSpHeader, constraint: &str| {
+            assert!(matches!(
+                header.validate_sizes(),
+                Err(IpccDataError::BootSpHeaderInconsistent(c))
+                    if c == constraint
+            ));
+        };
+
+        let mut bad = header;
+        bad.data_size = 0;
+        inconsistent(&bad, "data size is zero");
+
+        let mut bad = header;
+        bad.image_size = 0;
+        inconsistent(&bad, "image size is zero");
+
+        let mut bad = header;
+        bad.image_size = 0x3000;
+        bad.data_size = 0x3000;
+        inconsistent(&bad, "image size exceeds target size");
+
+        let mut bad = header;
+        bad.data_size = 0x800;
+        inconsistent(&bad, "uncompressed image size differs from data size");
+
+        //
+        // A compressed image is (of course) of a different size than its data.
+        //
+        bad.flags = u64::from(BootSpHeader::FLAG_COMPRESSED);
+        bad.validate_sizes().unwrap();
+    }
+
+    #[test]
+    fn stack_lines() {
+        let lines = decode(&v2_trap()).stack_lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "#0  die+0x43   0xfffffffffbc3a1d0",
+                "#1  trap+0x21  0xfffffffffbc3b000",
+            ]
+        );
+
+        //
+        // The depth is aligned, too.
+        //
+        let mut data = decode(&v2_trap());
+        data.stack = (0..11).map(|_| StackFrame::raw(Addr(0x1000))).collect();
+
+        let lines = data.stack_lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "#0   0x1000  0x0000000000001000");
+        assert_eq!(lines[10], "#10  0x1000  0x0000000000001000");
+
+        data.stack.clear();
+        assert_eq!(data.stack_lines().count(), 0);
+    }
+
+    #[test]
+    fn fully_symbolicated() {
+        let mut data = decode(&v2_trap());
+        assert!(data.is_fully_symbolicated());
+        assert!(data.stack.iter().all(StackFrame::is_symbolicated));
+
+        data.stack.push(StackFrame::raw(Addr(0xfffffffffbc3c000)));
+        assert!(!data.stack[2].is_symbolicated());
+        assert!(!data.is_fully_symbolicated());
+
+        data.stack.clear();
+        assert!(data.is_fully_symbolicated());
+    }
+
+    //
+    // Offsets into V2 panic data.
+    //
+    const V2_HRESTIME: usize = 15;
+
+    fn set_u64(d: &mut [u8], offset: usize, val: u64) {
+        d[offset..offset + 8].copy_from_slice(&val.to_le_bytes());
+    }
+
+    #[test]
+    fn invalid_nsec() {
+        let intact = decode(&v2_trap());
+
+        for nsec in [1_000_000_000, u64::from(u32::MAX) + 1, u64::MAX] {
+            let mut d = v2_trap();
+            set_u64(&mut d, V2_HRESTIME + 8, nsec);
+
+            let data = decode(&d);
+            assert!(data.time.is_none());
+            assert!(data.time_invalid);
+            assert_eq!(
+                data.hrtime.map(|t| t.0),
+                intact.hrtime.as_ref().map(|t| t.0)
+            );
+            assert_eq!(data.message, intact.message);
+            assert_eq!(
+                format!("{:?}", data.stack),
+                format!("{:?}", intact.stack)
+            );
+        }
+
+        assert!(!intact.time_invalid);
+    }
+
+    #[test]
+    fn monotonic_duration() {
+        use core::time::Duration;
+
+        let earlier = MonotonicNanoseconds(1_500_000_000);
+        let later = MonotonicNanoseconds(4_000_000_001);
+
+        assert_eq!(later.as_duration(), Duration::new(4, 1));
+        assert_eq!(later - earlier, Duration::new(2, 500_000_001));
+        assert_eq!(earlier - later, Duration::ZERO);
+        assert!(earlier < later);
+    }
+
+    #[test]
+    fn top_frame() {
+        let mut data = decode(&v2_trap());
+        assert!(core::ptr::eq(data.top_frame().unwrap(), &data.stack[0]));
+        assert_eq!(data.top_symbol(), Some("die"));
+
+        data.stack[0] = StackFrame::raw(Addr(0xfffffffffbc3a1d0));
+        assert!(data.top_frame().is_some());
+        assert_eq!(data.top_symbol(), None);
+
+        data.stack.clear();
+        assert!(data.top_frame().is_none());
+        assert_eq!(data.top_symbol(), None);
+    }
+
+    //
+    // Writes the given frame into the stack of V1 panic data.
+    //
+    fn set_v1_frame(d: &mut [u8], ndx: usize, frame: &StackFrame) {
+        let offset = IPCC_PANIC_V1_STACK_OFFSET + ndx * IPCC_PANIC_V1_STACK_LEN;
+        let entry = &mut d[offset..offset + IPCC_PANIC_V1_STACK_LEN];
+        let symbol = frame.symbol.as_deref().unwrap_or("").as_bytes();
+        let (sym, rest) = entry.split_at_mut(IPCC_PANIC_V1_SYMLEN);
+
+        sym.fill(0);
+        sym[..symbol.len()].copy_from_slice(symbol);
+        rest[..8].copy_from_slice(&frame.address.0.to_le_bytes());
+        rest[8..].copy_from_slice(&frame.offset.to_le_bytes());
+    }
+
+    #[test]
+    fn stack_order() {
+        //
+        // The same stack, as V1 and as V2 panic data, is in the same order
+        // (and has the same signature).
+        //
+        let v2 = decode(&v2_trap());
+        let mut d = v1_call();
+        d[1..3].copy_from_slice(&v2.raw_cause.to_le_bytes());
+        d[V1_STACKIDX] = v2.stack.len() as u8;
+
+        for (ndx, frame) in v2.stack.iter().enumerate() {
+            set_v1_frame(&mut d, ndx, frame);
+        }
+
+        let v1 = decode(&d);
+        assert_eq!(format!("{:?}", v1.stack), format!("{:?}", v2.stack));
+        assert_eq!(v1.top_symbol(), Some("die"));
+        assert_eq!(v1.stack_signature(), v2.stack_signature());
+    }
+
+    #[test]
+    fn v1_truncated() {
+        let intact = decode(&v1_call());
+        assert!(!intact.truncated);
+
+        //
+        // Truncated anywhere short of its stack, V1 panic data can't be
+        // decoded...
+        //
+        for len in [1, 12, V1_MESSAGE + 10, IPCC_PANIC_V1_STACK_OFFSET - 1] {
+            assert!(matches!(
+                PanicData::from_slice(&v1_call()[..len]),
+                Err(IpccDataError::PayloadTooShort { got, need })
+                    if got == len && need == IPCC_PANIC_V1_STACK_OFFSET
+            ));
+        }
+
+        //
+        // ...but beyond that, it is decoded as far as it is present.
+        //
+        for (len, frames) in [
+            (IPCC_PANIC_V1_STACK_OFFSET, 0),
+            (IPCC_PANIC_V1_STACK_OFFSET + IPCC_PANIC_V1_STACK_LEN - 1, 0),
+            (IPCC_PANIC_V1_STACK_OFFSET + IPCC_PANIC_V1_STACK_LEN, 1),
+            (IPCC_PANIC_V1_DATA_OFFSET, 1),
+            (IPCC_PANIC_V1_LEN - 1, 1),
+        ] {
+            let data = decode(&v1_call()[..len]);
+            assert!(data.truncated);
+            assert_eq!(data.cause, intact.cause);
+            assert_eq!(data.pc.0, intact.pc.0);
+            assert_eq!(data.message, intact.message);
+            assert_eq!(
+                format!("{:?}", data.stack),
+                format!("{:?}", &intact.stack[..frames])
+            );
+        }
+
+        //
+        // The same is true of truncated V1 panic data that suffers from
+        // hubris#1554, the version of which must be inferred from what
+        // remains.
+        //
+        for len in [
+            IPCC_PANIC_V1_STACK_OFFSET + IPCC_PANIC_V1_STACK_LEN,
+            600,
+            IPCC_PANIC_V1_LEN - 1,
+        ] {
+            let data = decode(&v1_call()[2..len]);
+            assert!(matches!(data.version, PanicDataVersion::Inferred(1)));
+            assert!(data.truncated);
+            assert_eq!(data.raw_cause, 0xca11);
+            assert_eq!(data.message, intact.message);
+            assert_eq!(
+                format!("{:?}", data.stack),
+                format!("{:?}", intact.stack)
+            );
+        }
+    }
+
+    #[test]
+    fn cause_classification() {
+        for (cause, early_boot, trap, explicit) in [
+            (PanicCause::Call, false, false, true),
+            (PanicCause::Trap, false, true, false),
+            (PanicCause::UserTrap, false, true, false),
+            (PanicCause::EarlyBoot, true, false, false),
+            (PanicCause::EarlyBootPROM, true, false, false),
+            (PanicCause::EarlyBootTrap, true, true, false),
+            (PanicCause::EarlyBootUnknown, true, false, false),
+            (PanicCause::EarlyBootOther(0x42), true, false, false),
+            (PanicCause::Unknown(0x1234), false, false, false),
+        ] {
+            assert_eq!(cause.is_early_boot(), early_boot, "{cause}");
+            assert_eq!(cause.is_trap(), trap, "{cause}");
+            assert_eq!(cause.is_explicit(), explicit, "{cause}");
+        }
+    }
+
+    #[test]
+    fn stack_depth() {
+        let mut data = decode(&v2_trap());
+        assert_eq!(data.stack_depth(), 2);
+        assert_eq!(data.symbolicated_frame_count(), 2);
+
+        data.stack.push(StackFrame::raw(Addr(0xfffffffffbc3c000)));
+        assert_eq!(data.stack_depth(), 3);
+        assert_eq!(data.symbolicated_frame_count(), 2);
+
+        data.stack.clear();
+        assert_eq!(data.stack_depth(), 0);
+        assert_eq!(data.symbolicated_frame_count(), 0);
+    }
+
+    #[test]
+    fn from_bytes_multi() {
+        let mut d = [v1_call(), v2_trap(), v2_hubris1554()].concat();
+        d.resize(d.len() + 64, 0);
+
+        assert_eq!(
+            format!("{:?}", PanicData::from_bytes_multi(&d).unwrap()),
+            format!(
+                "{:?}",
+                [
+                    decode(&v1_call()),
+                    decode(&v2_trap()),
+                    decode(&v2_hubris1554())
+                ]
+            )
+        );
+
+        assert!(PanicData::from_bytes_multi(&[]).unwrap().is_empty());
+        assert!(PanicData::from_bytes_multi(&[0; 64]).unwrap().is_empty());
+
+        //
+        // A partial payload at the end of the buffer is an error, as is a
+        // payload that fails to decode.
+        //
+        let d = [&v2_trap()[..], &v1_call()[..600]].concat();
+        assert!(matches!(
+            PanicData::from_bytes_multi(&d),
+            Err(IpccDataError::PayloadTooShort {
+                got: 600,
+                need: IPCC_PANIC_V1_LEN
+            })
+        ));
+
+        let d = [&v1_call()[..], &v2_trap()[..400]].concat();
+        assert!(matches!(
+            PanicData::from_bytes_multi(&d),
+            Err(IpccDataError::Deserialize(_))
+        ));
+    }
+
+    #[test]
+    fn from_bytes_counted() {
+        let junk = [0xba, 0xdc, 0x0f, 0xfe];
+
+        for (d, len) in [
+            (v1_call(), IPCC_PANIC_V1_LEN),
+            (v1_call()[2..].to_vec(), IPCC_PANIC_V1_LEN - 2),
+            (v2_trap(), 434),
+            (v2_hubris1554(), 432),
+        ] {
+            let trailing = [&d[..], &junk[..]].concat();
+
+            for d in [&d[..], &trailing[..]] {
+                let (_, consumed) =
+                    PanicData::from_bytes_counted(d).unwrap().unwrap();
+                assert_eq!(consumed, len);
+            }
+        }
+
+        assert!(PanicData::from_bytes_counted(&[0; 16]).unwrap().is_none());
+    }
+
+    #[test]
+    fn redact_message() {
+        let mut data = decode(&v1_call());
+        data.redact_message(|m| m.to_uppercase());
+        assert_eq!(
+            data.message.as_deref(),
+            Some("FORCED CRASH DUMP INITIATED AT USER REQUEST")
+        );
+
+        data.message = Some(
+            "failed to open \"/etc/passwd\" for root@oxide (via /dev/null)"
+                .to_string(),
+        );
+        data.redact_message_default();
+        assert_eq!(
+            data.message.as_deref(),
+            Some("failed to open <redacted> for <redacted> (via <redacted>")
+        );
+
+        data.message = Some("no\tpaths\n here".to_string());
+        data.redact_message_default();
+        assert_eq!(data.message.as_deref(), Some("no\tpaths\n here"));
+
+        data.message = None;
+        data.redact_message(|_| unreachable!());
+        assert_eq!(data.message, None);
+    }
+
+    #[test]
+    fn register() {
+        let data = decode(&v2_trap());
+        assert_eq!(data.register(Register::trapno), Some(0xe));
+        assert_eq!(data.register(Register::err), Some(2));
+        assert_eq!(data.register(Register::rsp), Some(0));
+
+        assert_eq!(decode(&v1_call()).register(Register::rip), None);
+    }
+
+    #[test]
+    fn max_stack_frames() {
+        let opts = PanicDataOptions::new().max_stack_frames(1);
+
+        for (d, truncated) in [(v1_call(), false), (v2_trap(), true)] {
+            let data = PanicData::from_slice_with(&d, &opts).unwrap().unwrap();
+            assert_eq!(
+                format!("{:?}", data.stack),
+                format!("{:?}", &decode(&d).stack[..1])
+            );
+            assert_eq!(data.stack_truncated, truncated);
+        }
+
+        assert!(!decode(&v2_trap()).stack_truncated);
+    }
+
+    const V2_NITEMS: usize = IPCC_PANIC_V2_HEADER_LEN - 4;
+
+    #[test]
+    fn implausible_item_count() {
+        let mut d = v2_payload(&[]);
+        d[V2_NITEMS..][..2].copy_from_slice(&u16::MAX.to_le_bytes());
+        d.extend_from_slice(&[0; 8]);
+
+        assert!(matches!(
+            PanicData::from_slice(&d),
+            Err(IpccDataError::ImplausibleItemCount {
+                nitems: u16::MAX,
+                len: 8
+            })
+        ));
+
+        //
+        // An absurd item count is rejected before any items are decoded,
+        // whatever the options.
+        //
+        let mut d = v2_trap();
+        d[V2_NITEMS..][..2].copy_from_slice(&u16::MAX.to_le_bytes());
+        let opts = PanicDataOptions::new().max_stack_frames(1);
+        assert!(matches!(
+            PanicData::from_slice_with(&d, &opts),
+            Err(IpccDataError::ImplausibleItemCount {
+                nitems: u16::MAX,
+                ..
+            })
+        ));
+
+        //
+        // An item count that is merely plausible is decoded.
+        //
+        let mut d = v2_payload(&[]);
+        d[V2_NITEMS..][..2].copy_from_slice(&2u16.to_le_bytes());
+        d.extend_from_slice(&[0, 3, 0, 0, 3, 0]);
+
+        let data = decode(&d);
+        assert!(data.items_len_mismatch);
+        assert!(!data.nop_data);
+        assert!(data.message.is_none());
+    }
+
+    const V2_ITEMS_LEN: usize = IPCC_PANIC_V2_HEADER_LEN - 2;
+
+    #[test]
+    fn items_len_mismatch() {
+        assert!(!decode(&v2_trap()).items_len_mismatch);
+
+        let mut d = v2_trap();
+        d[V2_ITEMS_LEN..][..2].copy_from_slice(&114u16.to_le_bytes());
+
+        let data = decode(&d);
+        assert!(data.items_len_mismatch);
+        assert_eq!(
+            format!("{:?}", data.stack),
+            format!("{:?}", decode(&v2_trap()).stack)
+        );
+
+        let opts = PanicDataOptions::new().require_items_len(true);
+        PanicData::from_slice_with(&v2_trap(), &opts).unwrap();
+
+        assert!(matches!(
+            PanicData::from_slice_with(&d, &opts),
+            Err(IpccDataError::ItemsLengthMismatch {
+                expected: 114,
+                got: 115
+            })
+        ));
+    }
+
+    const V1_CPUID: usize = 7;
+    const V2_HRTIME: usize = 7;
+    const V2_REGISTERS: usize = 75;
+
+    #[test]
+    fn infer_version() {
+        let opts = PanicDataOptions::default();
+        let infer = |d: &[u8]| {
+            let version = infer_panic_version(d, &opts).unwrap();
+            assert!(version.is_inferred());
+            version.number()
+        };
+
+        assert_eq!(infer(&v1_call()), 1);
+        assert_eq!(infer(&v2_trap()), 2);
+
+        //
+        // V1 panic data must have a plausible CPU ID...
+        //
+        let mut d = v1_call();
+        d[V1_CPUID..][..4].copy_from_slice(&MAX_PLAUSIBLE_CPUID.to_le_bytes());
+        assert_eq!(infer(&d), 1);
+
+        let cpuid = MAX_PLAUSIBLE_CPUID + 1;
+        d[V1_CPUID..][..4].copy_from_slice(&cpuid.to_le_bytes());
+        assert_eq!(infer(&d), 2);
+
+        //
+        // ...and valid symbols.
+        //
+        let mut d = v1_call();
+        d[IPCC_PANIC_V1_STACK_OFFSET] = 0xff;
+        assert_eq!(infer(&d), 2);
+
+        //
+        // The symbols of V1 panic data overlap the registers and items of V2
+        // panic data, which can therefore be valid UTF-8 by accident (as
+        // here, where the registers are zero and the message is ASCII).  Such
+        // V2 panic data is distinguished only by its would-be V1 CPU ID (that
+        // is, the low 32 bits of its hrtime), and is taken to be V1 if that
+        // is plausible.
+        //
+        let mut d =
+            v2_payload(&[v2_item(ITEM_MESSAGE, b"recursive mutex enter")]);
+        d[V2_REGISTERS..V2_NITEMS].fill(0);
+        assert_eq!(infer(&d), 2);
+
+        set_u64(&mut d, V2_HRTIME, u64::from(MAX_PLAUSIBLE_CPUID));
+        assert_eq!(infer(&d), 1);
+
+        //
+        // Panic data too short to be V1 may nonetheless be inferred to be
+        // (truncated) V1 -- even if a symbol is cut short mid-character.
+        //
+        assert_eq!(infer(&v1_call()[..600]), 1);
+
+        let mut d = v1_call()[..IPCC_PANIC_V1_STACK_OFFSET + 10].to_vec();
+        *d.last_mut().unwrap() = 0xc3;
+        assert_eq!(infer(&d), 1);
+
+        *d.last_mut().unwrap() = 0xff;
+        assert_eq!(infer(&d), 2);
+    }
+
+    #[test]
+    fn max_plausible_cpuid() {
+        let mut d = v1_call();
+        d[V1_CPUID..][..4].copy_from_slice(&1000u32.to_le_bytes());
+
+        let default = PanicDataOptions::default();
+        let opts = PanicDataOptions::new().max_plausible_cpuid(1023);
+
+        let infer = |d: &[u8], opts| infer_panic_version(d, opts).unwrap();
+        assert_eq!(infer(&d, &default).number(), 2);
+        assert_eq!(infer(&d, &opts).number(), 1);
+
+        //
+        // This is borne out when decoding panic data that suffers from
+        // hubris#1554 from such a CPU.
+        //
+        let data = PanicData::from_slice_with(&d[2..], &opts).unwrap().unwrap();
+        assert!(matches!(data.version, PanicDataVersion::Inferred(1)));
+        assert_eq!(data.cpuid.0, 1000);
+
+        d[V1_CPUID..][..4].copy_from_slice(&1024u32.to_le_bytes());
+        assert_eq!(infer(&d, &opts).number(), 2);
+    }
+
+    #[test]
+    fn cpuid_plausible() {
+        assert!(Cpuid(0).is_plausible());
+        assert!(Cpuid(MAX_PLAUSIBLE_CPUID).is_plausible());
+        assert!(!Cpuid(MAX_PLAUSIBLE_CPUID + 1).is_plausible());
+    }
+
+    #[test]
+    fn try_from() {
+        let d = v1_call();
+        assert_eq!(
+            format!("{:?}", PanicData::try_from(&d[..]).unwrap()),
+            format!("{:?}", decode(&d))
+        );
+
+        let d = v2_trap();
+        assert_eq!(
+            format!("{:?}", PanicData::try_from(d.clone()).unwrap()),
+            format!("{:?}", decode(&d))
+        );
+
+        assert!(matches!(
+            PanicData::try_from(vec![0u8; 16]),
+            Err(IpccDataError::EmptyPayload)
+        ));
+        assert!(matches!(
+            PanicData::try_from(&[0u8; 0][..]),
+            Err(IpccDataError::EmptyPayload)
+        ));
+        assert!(matches!(
+            PanicData::try_from(&d[..100]),
+            Err(IpccDataError::PayloadTooShort { .. })
+        ));
+    }
+
+    #[test]
+    fn registers_unordered() {
+        let mut data = decode(&v2_trap());
+        data.registers.as_mut().unwrap().reverse();
+        assert_eq!(data, decode(&v2_trap()));
+
+        data.registers.as_mut().unwrap().insert(Register::rsp, 1);
+        assert_ne!(data, decode(&v2_trap()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash() {
+        use std::collections::HashSet;
+
+        let mut set = HashSet::new();
+        assert!(set.insert(decode(&v2_trap())));
+        assert!(!set.insert(decode(&v2_trap())));
+        assert!(set.insert(decode(&v2_hubris1554())));
+
+        //
+        // As with equality, the order of the registers is not considered...
+        //
+        let mut data = decode(&v2_trap());
+        data.registers.as_mut().unwrap().reverse();
+        assert!(!set.insert(data));
+
+        //
+        // ...but the time is.
+        //
+        let mut data = decode(&v2_trap());
+        data.time = Some(AdjustedTime {
+            sec: 1_700_000_001,
+            nsec: 500,
+        });
+        assert!(set.insert(data));
+    }
+
+    #[test]
+    fn message_str() {
+        let data = decode(&v2_trap());
+        let message = data.message_str().unwrap();
+        assert_eq!(message, V2_TRAP_MESSAGE);
+        assert_eq!(message.as_ptr(), data.message.as_ref().unwrap().as_ptr());
+
+        assert_eq!(decode(&v2_payload(&[])).message_str(), None);
+    }
+
+    #[test]
+    fn from_bytes_strict() {
+        for d in [v1_call(), v2_trap()] {
+            assert_eq!(
+                PanicData::from_bytes_strict(d.clone()).unwrap(),
+                Some(decode(&d))
+            );
+        }
+
+        assert!(matches!(
+            PanicData::from_bytes_strict(v2_hubris1554()),
+            Err(IpccDataError::VersionNotDetermined(2))
+        ));
+
+        assert!(PanicData::from_bytes_strict(vec![0; 16]).unwrap().is_none());
+    }
+
+    #[test]
+    fn empty_payload() {
+        assert!(is_empty_payload(&[]));
+        assert!(is_empty_payload(&[0; 512]));
+        assert!(!is_empty_payload(&[0, 0, 1, 0]));
+        assert!(!is_empty_payload(&v1_call()));
+
+        assert!(PanicData::from_slice(&[0; 512]).unwrap().is_none());
+    }
+
+    #[test]
+    fn architecture() {
+        assert_eq!(Architecture::default(), Architecture::Amd64);
+        assert_eq!(Architecture::Amd64.registers(), Register::all());
+
+        let opts = PanicDataOptions::new().architecture(Architecture::Amd64);
+
+        for d in [v1_call(), v2_trap(), v2_hubris1554()] {
+            let data = PanicData::from_slice_with(&d, &opts).unwrap();
+            assert_eq!(data, Some(decode(&d)));
+        }
+    }
+
+    #[test]
+    fn format_registers() {
+        let data = decode(&v2_trap());
+        let expected = concat!(
+            "\trdi:                0 rsi:                0 rdx:                0\n",
+            "\trcx:                0  r8:                0  r9:                0\n",
+            "\trax:                0 rbx:                0 rbp:                0\n",
+            "\tr10:                0 r11:                0 r12:                0\n",
+            "\tr13:                0 r14:                0 r15:                0\n",
+            "\tfsb:                0 gsb:                0  ds:                0\n",
+            "\t es:                0  fs:                0  gs:                0\n",
+            "\ttrp:                e err:                2 rip: fffffffffbc3a1d0\n",
+            "\t cs:               30 rfl:                0 rsp:                0\n",
+            "\t ss:                0\n",
+        );
+
+        assert_eq!(data.format_registers(), expected);
+        assert_eq!(decode(&v1_call()).format_registers(), "");
+
+        //
+        // The registers are displayed as part of the panic data.
+        //
+        let display = data.to_string();
+        assert!(display.contains(&format!("\n\n{expected}\n")));
+    }
+
+    #[test]
+    fn to_fields() {
+        let fields = decode(&v2_trap()).to_fields();
+
+        for (name, value) in [
+            ("version", "2"),
+            ("version_inferred", "false"),
+            ("cause", "IPCC_PANIC_TRAP"),
+            ("error", "0x2"),
+            ("cpuid", "3"),
+            ("thread", "0xfffffe0001e0fc20"),
+            ("addr", "0x0"),
+            ("pc", "0xfffffffffbc3a1d0"),
+            ("fp", "0xfffffe0001e0fa40"),
+            ("rp", "0xfffffe0001e0fa50"),
+            ("hrtime", "1234567890"),
+            ("time", "1700000000.000000500"),
+            ("message", V2_TRAP_MESSAGE),
+            ("reg.trapno", "0xe"),
+            ("reg.rip", "0xfffffffffbc3a1d0"),
+            ("reg.rsp", "0x0"),
+            ("stack", "die+0x43,trap+0x21"),
+        ] {
+            assert_eq!(
+                fields.get(name).map(String::as_str),
+                Some(value),
+                "{name}"
+            );
+        }
+
+        let fields = decode(&v1_call()).to_fields();
+        assert_eq!(fields["stack"], "panicsys+0x12");
+        assert!(!fields.contains_key("hrtime"));
+        assert!(!fields.contains_key("time"));
+        assert!(!fields.contains_key("reg.rip"));
+    }
+
+    #[test]
+    fn untrimmed_message() {
+        let mut d = v1_call();
+        let message = &mut d[V1_MESSAGE..][..IPCC_PANIC_V1_MSGLEN];
+        message.fill(0);
+        message[..5].copy_from_slice(b"ab\0cd");
+
+        assert_eq!(decode(&d).message_str(), Some("ab\0cd"));
+
+        let opts = PanicDataOptions::new().trim_messages(false);
+        let data = PanicData::from_slice_with(&d, &opts).unwrap().unwrap();
+        assert_eq!(
+            data.message.unwrap().as_bytes(),
+            &d[V1_MESSAGE..][..IPCC_PANIC_V1_MSGLEN]
+        );
+    }
+
+    #[test]
+    fn symbolize_addresses() {
+        let mut data = decode(&v2_trap());
+        let pc = data.pc;
+
+        data.symbolize(
+            |addr| match addr == pc {
+                true => Some(("die".to_string(), 0x43)),
+                false => None,
+            },
+            false,
+        );
+
+        assert_eq!(data.pc_symbol(), Some("die"));
+        assert_eq!(data.addr_symbols.pc, Some(("die".to_string(), 0x43)));
+        assert_eq!(data.thread_symbol(), None);
+        assert_eq!(data.fp_symbol(), None);
+        assert_eq!(data.rp_symbol(), None);
+
+        //
+        // As with frames, a resolved address is left alone unless forced.
+        //
+        let other = |_: Addr| Some(("other".to_string(), 0));
+
+        data.symbolize(other, false);
+        assert_eq!(data.pc_symbol(), Some("die"));
+        assert_eq!(data.thread_symbol(), Some("other"));
+        assert_eq!(data.top_symbol(), Some("die"));
+
+        data.symbolize(other, true);
+        assert_eq!(data.pc_symbol(), Some("other"));
+        assert_eq!(data.top_symbol(), Some("other"));
+    }
+
+    #[test]
+    fn summary() {
+        let mut data = decode(&v2_trap());
+        assert_eq!(
+            data.summary(),
+            format!("IPCC_PANIC_TRAP cpu 3 at die+0x43: {V2_TRAP_MESSAGE:?}")
+        );
+
+        //
+        // The message is truncated (by characters, not bytes).
+        //
+        data.message = Some("\u{e9}".repeat(61));
+        assert_eq!(
+            data.summary(),
+            format!(
+                "IPCC_PANIC_TRAP cpu 3 at die+0x43: \"{}...\"",
+                "\u{e9}".repeat(60)
+            )
+        );
+
+        data.message = None;
+        data.stack.clear();
+        assert_eq!(data.summary(), "IPCC_PANIC_TRAP cpu 3");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn from_file() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("ipcc-data-{}.bin", std::process::id()));
+        std::fs::write(&path, v2_trap()).unwrap();
+
+        let data = PanicData::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(data.unwrap(), Some(decode(&v2_trap())));
+
+        let path = dir.join("ipcc-data-nonexistent.bin");
+        assert!(matches!(
+            PanicData::from_file(&path),
+            Err(IpccDataError::ReadFile { path: p, .. }) if p == path
+        ));
+    }
+
+    #[test]
+    fn fixtures() {
+        //
+        // The fixtures in tests/fixtures are the payloads that our builders
+        // construct; if the builders change, so must the fixtures.
+        //
+        let fixtures: &[(&[u8], Vec<u8>)] = &[
+            (include_bytes!("../tests/fixtures/v1-call.bin"), v1_call()),
+            (include_bytes!("../tests/fixtures/v2-trap.bin"), v2_trap()),
+            (
+                include_bytes!("../tests/fixtures/v2-hubris1554.bin"),
+                v2_hubris1554(),
+            ),
+        ];
+
+        for (fixture, built) in fixtures {
+            assert_eq!(*fixture, &built[..]);
+        }
+
+        let zero = include_bytes!("../tests/fixtures/all-zero.bin");
+        assert!(zero.iter().all(|&b| b == 0));
+        assert_eq!(PanicData::from_bytes(zero.to_vec()).unwrap(), None);
+    }
+
+    #[test]
+    fn needs_1554_fixup() {
+        assert!(payload_needs_1554_fixup(&v2_hubris1554()));
+        assert!(payload_needs_1554_fixup(&v1_call()[2..]));
+
+        assert!(!payload_needs_1554_fixup(&v2_trap()));
+        assert!(!payload_needs_1554_fixup(&v1_call()));
+        assert!(!payload_needs_1554_fixup(&[]));
+        assert!(!payload_needs_1554_fixup(&[0; 16]));
+
+        assert!(decode(&v2_hubris1554()).version.is_inferred());
+        assert!(!decode(&v2_trap()).version.is_inferred());
+    }
+
+    #[test]
+    fn early_boot_causes() {
+        for (wire, cause, subtype) in [
+            (0xeb00u16, PanicCause::EarlyBoot, Some(0x00)),
+            (0xeb97, PanicCause::EarlyBootPROM, Some(0x97)),
+            (0xeba9, PanicCause::EarlyBootTrap, Some(0xa9)),
+            (0xebff, PanicCause::EarlyBootUnknown, None),
+            (0xeb42, PanicCause::EarlyBootOther(0x42), Some(0x42)),
+        ] {
+            assert_eq!(PanicCause::from(wire), cause);
+            assert_eq!(cause.wire_value(), wire);
+            assert_eq!(cause.early_boot_subtype(), subtype);
+            assert!(cause.is_early_boot());
+        }
+
+        assert_eq!(
+            PanicCause::EarlyBootOther(0x42).to_string(),
+            "<Unknown early boot cause 0x42>"
+        );
+        assert_eq!(PanicCause::Trap.early_boot_subtype(), None);
+
+        //
+        // Panic data that suffers from hubris#1554 has lost the subtype.
+        //
+        let mut d = v2_trap();
+        d[1..3].copy_from_slice(&0xeb97u16.to_le_bytes());
+        assert_eq!(decode(&d[2..]).cause, PanicCause::EarlyBootUnknown);
+    }
+
+    #[test]
+    fn c_identifier() {
+        for cause in [
+            PanicCause::Call,
+            PanicCause::Trap,
+            PanicCause::UserTrap,
+            PanicCause::EarlyBoot,
+            PanicCause::EarlyBootPROM,
+            PanicCause::EarlyBootTrap,
+        ] {
+            let id = cause.c_identifier().unwrap();
+            assert!(id.starts_with("IPCC_PANIC_"));
+            assert_eq!(id, cause.to_string());
+            assert_eq!(id.parse::<PanicCause>().unwrap(), cause);
+        }
+
+        for cause in [
+            PanicCause::EarlyBootUnknown,
+            PanicCause::EarlyBootOther(0x42),
+            PanicCause::Unknown(0x1234),
+        ] {
+            assert_eq!(cause.c_identifier(), None);
+        }
+    }
+
+    #[test]
+    fn missing_registers() {
+        let mut data = decode(&v2_trap());
+        assert!(data.missing_registers().is_empty());
+
+        let registers = data.registers.as_mut().unwrap();
+        registers.shift_remove(&Register::ds);
+        registers.shift_remove(&Register::r15);
+        assert_eq!(data.missing_registers(), [Register::r15, Register::ds]);
+
+        assert!(decode(&v1_call()).missing_registers().is_empty());
+    }
+
+    #[test]
+    fn zero_times() {
+        let mut d = v2_trap();
+        d[V2_HRTIME..V2_HRESTIME + 16].fill(0);
+
+        let data = decode(&d);
+        assert_eq!(data.hrtime, None);
+        assert_eq!(data.time, None);
+        assert!(!data.time_invalid);
+
+        //
+        // A time that is merely near zero is still a time.
+        //
+        set_u64(&mut d, V2_HRTIME, 1);
+        set_u64(&mut d, V2_HRESTIME + 8, 1);
+
+        let data = decode(&d);
+        assert_eq!(data.hrtime, Some(MonotonicNanoseconds(1)));
+        assert_eq!(data.time, Some(AdjustedTime { sec: 0, nsec: 1 }));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json_value() {
+        use serde_json::json;
+
+        let json = decode(&v2_trap()).to_json_value();
+        assert_eq!(json["version"], 2);
+        assert_eq!(json["version_inferred"], false);
+        assert_eq!(json["cause"], "IPCC_PANIC_TRAP");
+        assert_eq!(json["error"], "0x2");
+        assert_eq!(json["cpuid"], 3);
+        assert_eq!(json["hrtime"], 1234567890);
+        assert_eq!(json["time"], json!({ "sec": 1700000000, "nsec": 500 }));
+        assert_eq!(json["pc"], "0xfffffffffbc3a1d0");
+        assert_eq!(json["message"], V2_TRAP_MESSAGE);
+        assert_eq!(json["registers"]["trapno"], "0xe");
+        assert_eq!(
+            json["stack"][1],
+            json!({
+                "address": "0xfffffffffbc3b000",
+                "symbol": "trap",
+                "offset": "0x21",
+            })
+        );
+
+        let json = decode(&v1_call()).to_json_value();
+        assert!(json["hrtime"].is_null());
+        assert!(json["time"].is_null());
+        assert!(json["registers"].is_null());
+    }
+
+    #[test]
+    fn bootsp_header_find_in() {
+        let header = bootsp_header("rpool", "phase2").to_bytes();
+
+        //
+        // We precede the header with a decoy:  the magic, but not a header.
+        //
+        let mut buf = vec![0xa5; 37];
+        buf[5..9].copy_from_slice(&BootSpHeader::MAGIC.to_le_bytes());
+        buf.extend_from_slice(&header);
+        buf.extend_from_slice(&[0xa5; 64]);
+
+        let (offset, found) = BootSpHeader::find_in(&buf).unwrap();
+        assert_eq!(offset, 37);
+        assert_eq!(found.dataset_str(), "rpool");
+
+        assert!(BootSpHeader::find_in(&buf[38..]).is_none());
+        assert!(BootSpHeader::find_in(&buf[..37]).is_none());
+        assert!(BootSpHeader::find_in(&[]).is_none());
+    }
+
+    #[test]
+    fn bootsp_image_range() {
+        let mut header = BootSpHeader::new("rpool", "phase2").unwrap();
+        header.data_size = 0x8000;
+        header.image_size = 0x8000;
+
+        assert_eq!(header.image_offset(), BootSpHeader::HEADER_SIZE);
+        assert_eq!(header.image_range(), 0x1000..0x9000);
+        assert_eq!(header.data_range(), header.image_range());
+
+        let mut blob = header.to_bytes();
+        blob.extend_from_slice(&[0x5a; 0x8000]);
+        assert!(blob[header.image_range()].iter().all(|&b| b == 0x5a));
+        assert_eq!(header.image_range().end, blob.len());
+
+        //
+        // For a compressed image, what follows the header is smaller than
+        // the image.
+        //
+        header.flags = u64::from(BootSpHeader::FLAG_COMPRESSED);
+        header.data_size = 0x2345;
+
+        assert_eq!(header.image_range(), 0x1000..0x9000);
+        assert_eq!(header.data_range(), 0x1000..0x3345);
+
+        let mut blob = header.to_bytes();
+        blob.extend_from_slice(&[0x5a; 0x2345]);
+        assert!(blob[header.data_range()].iter().all(|&b| b == 0x5a));
+        assert_eq!(header.data_range().end, blob.len());
+    }
+
+    #[test]
+    fn annotate_panic_bytes_v2() {
+        let expected = concat!(
+            "0x0000  version\n",
+            "        02\n",
+            "0x0001  cause\n",
+            "        00 a9\n",
+            "0x0003  error\n",
+            "        02 00 00 00\n",
+            "0x0007  hrtime\n",
+            "        d2 02 96 49 00 00 00 00\n",
+            "0x000f  hrestime\n",
+            "        00 f1 53 65 00 00 00 00 f4 01 00 00 00 00 00 00\n",
+            "0x001f  cpuid\n",
+            "        03 00 00 00\n",
+            "0x0023  thread\n",
+            "        20 fc e0 01 00 fe ff ff\n",
+            "0x002b  addr (truncated)\n",
+            "        00 00 00 00 00\n",
+        );
+
+        assert_eq!(annotate_panic_bytes(&v2_trap()[..48], 2), expected);
+
+        let labels = |d: &[u8], version| {
+            annotate_panic_bytes(d, version)
+                .lines()
+                .filter(|l| l.starts_with("0x"))
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            labels(&v2_trap(), 2),
+            [
+                "0x0000  version",
+                "0x0001  cause",
+                "0x0003  error",
+                "0x0007  hrtime",
+                "0x000f  hrestime",
+                "0x001f  cpuid",
+                "0x0023  thread",
+                "0x002b  addr",
+                "0x0033  pc",
+                "0x003b  fp",
+                "0x0043  rp",
+                "0x004b  registers",
+                "0x013b  nitems",
+                "0x013d  items_len",
+                "0x013f  item[0] (Message)",
+                "0x017e  item[1] (StackEntry)",
+                "0x0194  item[2] (StackEntry)",
+                "0x01ab  item[3] (Ancillary)",
+            ]
+        );
+
+        let mut d = v1_call();
+        d.extend_from_slice(&[0; 4]);
+
+        let v1 = labels(&d, 1);
+        assert_eq!(v1.len(), 11 + IPCC_PANIC_V1_STACKS + 3);
+        assert_eq!(v1[11], "0x00b4  stack[0]");
+        assert_eq!(v1[v1.len() - 3], "0x03b4  dataidx");
+        assert_eq!(v1[v1.len() - 2], "0x03b5  data");
+        assert_eq!(v1[v1.len() - 1], "0x04b5  trailing");
+
+        //
+        // For a version that we don't know, every byte is trailing.
+        //
+        assert_eq!(labels(&v2_trap(), 3), ["0x0000  trailing"]);
+    }
+
+    #[test]
+    fn raw_registers() {
+        let mut d = v2_trap();
+        set_u64(&mut d, V2_REGISTERS, 0xfffffe0001e0fa80);
+        set_u64(&mut d, V2_REGISTERS + 8, 0xfffffffffbc3b0c0);
+
+        let data = decode(&d);
+        let raw = data.raw_registers().unwrap();
+        assert_eq!(raw.savfp, 0xfffffe0001e0fa80);
+        assert_eq!(raw.savpc, 0xfffffffffbc3b0c0);
+        assert_eq!(raw.trapno, 0xe);
+        assert_eq!(Some(raw.rip), data.register(Register::rip));
+
+        //
+        // The raw registers -- including those that aren't otherwise
+        // decoded -- survive encoding.
+        //
+        assert_eq!(data.to_v2_bytes().unwrap(), d);
+        assert_eq!(decode(&v1_call()).raw_registers(), None);
+    }
+
+    #[test]
+    fn stack_consistency() {
+        decode(&v2_trap()).validate_stack_consistency().unwrap();
+        decode(&v1_call()).validate_stack_consistency().unwrap();
+
+        let inconsistent = |data: PanicData, why: &str| {
+            assert!(matches!(
+                data.validate_stack_consistency(),
+                Err(IpccDataError::StackInconsistent(w)) if w == why
+            ));
+        };
+
+        //
+        // The program counter must be among the innermost frames:  dropping
+        // the innermost frame of our trap (die+0x43, at the program counter)
+        // leaves a stack that is inconsistent with it, as does burying it.
+        //
+        let mut data = decode(&v2_trap());
+        data.stack.remove(0);
+        inconsistent(data, "program counter not in innermost stack frames");
+
+        let mut data = decode(&v2_trap());
+        let frame = || StackFrame::raw(Addr(0xfffffffffbc3c000));
+        for _ in 1..TRAP_PC_FRAMES {
+            data.stack.insert(0, frame());
+        }
+        data.validate_stack_consistency().unwrap();
+        data.stack.insert(0, frame());
+        inconsistent(data, "program counter not in innermost stack frames");
+
+        let mut data = decode(&v2_trap());
+        data.stack.clear();
+        data.validate_stack_consistency().unwrap();
+
+        let mut data = decode(&v2_trap());
+        data.pc = Addr(0xfffffffffbc3a1d4);
+        inconsistent(data, "program counter differs from rip");
+
+        let mut data = decode(&v2_trap());
+        data.rp = Addr(0);
+        inconsistent(data, "register pointer is null for trap");
+
+        let mut data = decode(&v2_trap());
+        data.rp = Addr(0xfffffe0001e0fa30);
+        inconsistent(data, "register pointer is below frame pointer");
+
+        let mut data = decode(&v2_trap());
+        data.raw_registers.as_mut().unwrap().savfp = 0xfffffe0001e0fa80;
+        data.validate_stack_consistency().unwrap();
+        data.raw_registers.as_mut().unwrap().savfp = 0xfffffe0001e0fa48;
+        inconsistent(data, "register pointer is beyond saved frame pointer");
+
+        let mut data = decode(&v1_call());
+        data.stack.push(StackFrame::raw(Addr(0)));
+        inconsistent(data, "stack frame has null address and no symbol");
+    }
+
+    #[test]
+    fn addr_classification() {
+        for (addr, canonical, kernel, user) in [
+            (0xfffffffffbc3a1d0, true, true, false),
+            (0xffff_8000_0000_0000, true, true, false),
+            (0x0000_7fff_ffff_ffff, true, false, true),
+            (0x0000_0000_0040_1000, true, false, true),
+            (0, true, false, true),
+            (0x0000_8000_0000_0000, false, false, false),
+            (0xdead_beef_dead_beef, false, false, false),
+        ] {
+            let addr = Addr(addr);
+            assert_eq!(addr.is_canonical(), canonical, "{addr:#x}");
+            assert_eq!(addr.is_kernel(), kernel, "{addr:#x}");
+            assert_eq!(addr.is_user(), user, "{addr:#x}");
+        }
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::collection::vec as arb_vec;
+        use proptest::prelude::{any, Just, Strategy};
+        use proptest::sample::Index;
+        use proptest::{prop_assert, prop_assert_eq, prop_oneof, proptest};
+
+        //
+        // An item of V2 panic data that we generate.
+        //
+        #[derive(Clone, Debug)]
+        enum Item {
+            Nop,
+            Message(String),
+            StackEntry(u64, u64, String),
+            Ancillary(Vec<u8>),
+        }
+
+        fn item() -> impl Strategy<Value = Item> {
+            prop_oneof![
+                Just(Item::Nop),
+                "\\PC{0,32}".prop_map(Item::Message),
+                (any::<u64>(), any::<u64>(), "[a-z_][a-z0-9_]{0,31}")
+                    .prop_map(|(a, o, s)| Item::StackEntry(a, o, s)),
+                arb_vec(any::<u8>(), 0..32).prop_map(Item::Ancillary),
+            ]
+        }
+
+        fn encode(item: &Item) -> Vec<u8> {
+            match item {
+                Item::Nop => v2_item(ITEM_NOP, &[]),
+                Item::Message(message) => {
+                    v2_item(ITEM_MESSAGE, message.as_bytes())
+                }
+                Item::StackEntry(addr, offset, symbol) => {
+                    v2_stack_entry(*addr, *offset, symbol)
+                }
+                Item::Ancillary(data) => v2_item(ITEM_ANCILLARY, data),
+            }
+        }
+
+        fn frames(stack: &[StackFrame]) -> Vec<(u64, u64, Option<&str>)> {
+            stack
+                .iter()
+                .map(|f| (f.address.0, f.offset, f.symbol.as_deref()))
+                .collect()
+        }
+
+        proptest! {
+            //
+            // Arbitrary items decode to the messages, stack and ancillary
+            // data that they encode.
+            //
+            #[test]
+            fn v2_items_round_trip(items in arb_vec(item(), 0..16)) {
+                let encoded = items.iter().map(encode).collect::<Vec<_>>();
+                let data = decode(&v2_payload(&encoded));
+
+                let messages = items
+                    .iter()
+                    .filter_map(|i| match i {
+                        Item::Message(message) => Some(message.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                let message = match messages.len() {
+                    0 => None,
+                    _ => Some(messages.join("\n")),
+                };
+
+                let stack = items
+                    .iter()
+                    .filter_map(|i| match i {
+                        Item::StackEntry(addr, offset, symbol) => {
+                            Some((*addr, *offset, Some(symbol.as_str())))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                let ancillary = items
+                    .iter()
+                    .filter_map(|i| match i {
+                        Item::Ancillary(bytes) => Some(bytes.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                prop_assert_eq!(data.message, message);
+                prop_assert_eq!(frames(&data.stack), stack);
+                prop_assert_eq!(data.ancillary, ancillary);
+            }
+
+            //
+            // An item whose length is shorter than the item header itself is
+            // rejected, wherever it falls among the items.
+            //
+            #[test]
+            fn v2_items_short_len(
+                items in arb_vec(item(), 0..8),
+                ftype in 0u8..4,
+                len in 0u16..3
+            ) {
+                let mut encoded = items.iter().map(encode).collect::<Vec<_>>();
+                let mut short = vec![ftype];
+                short.extend_from_slice(&len.to_le_bytes());
+                encoded.push(short);
+
+                let index = items.len();
+                let err = PanicData::from_slice(&v2_payload(&encoded));
+                let rejected = matches!(
+                    err,
+                    Err(IpccDataError::InvalidItemLength { index: i, len: l })
+                        if i == index && l == len
+                );
+                prop_assert!(rejected, "unexpected result: {:?}", err);
+            }
+
+            //
+            // Arbitrary bytes following the header never cause a panic.
+            //
+            #[test]
+            fn v2_items_arbitrary(
+                nitems in any::<u16>(),
+                items_len in any::<u16>(),
+                items in arb_vec(any::<u8>(), 0..512)
+            ) {
+                let mut d = v2_payload(&[]);
+                d.truncate(IPCC_PANIC_V2_HEADER_LEN - 4);
+                d.extend_from_slice(&nitems.to_le_bytes());
+                d.extend_from_slice(&items_len.to_le_bytes());
+                d.extend_from_slice(&items);
+
+                let _ = PanicData::from_slice(&d);
+            }
+
+            //
+            // Nor does any truncation of valid panic data, whether or not it
+            // suffers from hubris#1554.
+            //
+            #[test]
+            fn v2_items_truncated(
+                items in arb_vec(item(), 0..16),
+                len in any::<Index>()
+            ) {
+                let encoded = items.iter().map(encode).collect::<Vec<_>>();
+                let d = v2_payload(&encoded);
+                let d = &d[..len.index(d.len() + 1)];
+
+                let _ = PanicData::from_slice(d);
+                let _ = PanicData::from_slice(d.get(2..).unwrap_or(&[]));
+            }
+        }
+    }
 }
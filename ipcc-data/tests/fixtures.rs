@@ -0,0 +1,196 @@
+//
+// Decodes each of the fixtures in tests/fixtures, checking that it decodes
+// to the panic data described in tests/fixtures/README.md.
+//
+
+#![cfg(feature = "std")]
+
+use ipcc_data::{
+    Addr, Cpuid, PanicCause, PanicData, PanicDataVersion, Register,
+};
+use std::path::PathBuf;
+
+struct Fixture {
+    name: &'static str,
+    version: PanicDataVersion,
+    cause: PanicCause,
+    cause_inferred: bool,
+    error_code: u32,
+    cpuid: u32,
+    hrtime: Option<u64>,
+    time: Option<(u64, u32)>,
+    thread: u64,
+    pc: u64,
+    fp: u64,
+    rp: u64,
+
+    // registers that are non-zero; if `None`, registers are absent
+    registers: Option<&'static [(Register, u64)]>,
+
+    message: &'static str,
+
+    // symbol, offset and address of each frame
+    stack: &'static [(&'static str, u64, u64)],
+
+    ancillary: &'static [&'static [u8]],
+}
+
+const V2_TRAP_REGISTERS: &[(Register, u64)] = &[
+    (Register::trapno, 0xe),
+    (Register::err, 2),
+    (Register::rip, 0xfffffffffbc3a1d0),
+    (Register::cs, 0x30),
+];
+
+const V2_TRAP_MESSAGE: &str =
+    "BAD TRAP: type=e (#pf Page fault) rp=fffffe0001e0fa50 addr=0";
+
+const V2_TRAP_STACK: &[(&str, u64, u64)] = &[
+    ("die", 0x43, 0xfffffffffbc3a1d0),
+    ("trap", 0x21, 0xfffffffffbc3b000),
+];
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "v1-call.bin",
+        version: PanicDataVersion::Determined(1),
+        cause: PanicCause::Call,
+        cause_inferred: false,
+        error_code: 0,
+        cpuid: 0,
+        hrtime: None,
+        time: None,
+        thread: 0xfffffe00016a2c20,
+        pc: 0xfffffffff7a0b3f2,
+        fp: 0xfffffe0001e0fa40,
+        rp: 0,
+        registers: None,
+        message: "forced crash dump initiated at user request",
+        stack: &[("panicsys", 0x12, 0xfffffffffbc3a1d0)],
+        ancillary: &[],
+    },
+    Fixture {
+        name: "v2-trap.bin",
+        version: PanicDataVersion::Determined(2),
+        cause: PanicCause::Trap,
+        cause_inferred: false,
+        error_code: 2,
+        cpuid: 3,
+        hrtime: Some(1234567890),
+        time: Some((1700000000, 500)),
+        thread: 0xfffffe0001e0fc20,
+        pc: 0xfffffffffbc3a1d0,
+        fp: 0xfffffe0001e0fa40,
+        rp: 0xfffffe0001e0fa50,
+        registers: Some(V2_TRAP_REGISTERS),
+        message: V2_TRAP_MESSAGE,
+        stack: V2_TRAP_STACK,
+        ancillary: &[&[1, 2, 3, 4]],
+    },
+    Fixture {
+        name: "v2-hubris1554.bin",
+        version: PanicDataVersion::Inferred(2),
+        cause: PanicCause::Trap,
+        cause_inferred: true,
+        error_code: 2,
+        cpuid: 3,
+        hrtime: Some(1234567890),
+        time: Some((1700000000, 500)),
+        thread: 0xfffffe0001e0fc20,
+        pc: 0xfffffffffbc3a1d0,
+        fp: 0xfffffe0001e0fa40,
+        rp: 0xfffffe0001e0fa50,
+        registers: Some(V2_TRAP_REGISTERS),
+        message: V2_TRAP_MESSAGE,
+        stack: V2_TRAP_STACK,
+        ancillary: &[&[1, 2, 3, 4]],
+    },
+];
+
+fn path(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "tests", "fixtures", name]
+        .iter()
+        .collect()
+}
+
+fn decode(name: &str) -> Option<PanicData> {
+    PanicData::from_file(path(name))
+        .unwrap_or_else(|err| panic!("failed to decode {}: {}", name, err))
+}
+
+#[test]
+fn fixtures() {
+    for f in FIXTURES {
+        let name = f.name;
+        let data = decode(name)
+            .unwrap_or_else(|| panic!("{} decoded to no panic data", name));
+
+        assert_eq!(data.version, f.version, "{name}: version");
+        assert_eq!(data.cause, f.cause, "{name}: cause");
+        assert_eq!(data.cause_inferred, f.cause_inferred, "{name}: inferred");
+        assert_eq!(data.error_code, f.error_code, "{name}: error code");
+        assert_eq!(data.cpuid, Cpuid(f.cpuid), "{name}: cpuid");
+        assert_eq!(data.hrtime.map(|t| t.0), f.hrtime, "{name}: hrtime");
+        assert_eq!(
+            data.time.as_ref().map(|t| (t.sec, t.nsec)),
+            f.time,
+            "{name}: time"
+        );
+        assert_eq!(data.thread, Addr(f.thread), "{name}: thread");
+        assert_eq!(data.pc, Addr(f.pc), "{name}: pc");
+        assert_eq!(data.fp, Addr(f.fp), "{name}: fp");
+        assert_eq!(data.rp, Addr(f.rp), "{name}: rp");
+        assert_eq!(data.message_str(), Some(f.message), "{name}: message");
+
+        match f.registers {
+            Some(expected) => {
+                for &reg in Register::all() {
+                    let val = expected
+                        .iter()
+                        .find(|(r, _)| *r == reg)
+                        .map_or(0, |(_, val)| *val);
+
+                    assert_eq!(data.register(reg), Some(val), "{name}: {reg}");
+                }
+            }
+            None => {
+                assert!(data.registers.is_none(), "{}: registers", name)
+            }
+        }
+
+        let stack = data
+            .stack
+            .iter()
+            .map(|frame| (frame.symbol.as_deref(), frame.offset, frame.address))
+            .collect::<Vec<_>>();
+
+        let expected = f
+            .stack
+            .iter()
+            .map(|&(symbol, offset, addr)| (Some(symbol), offset, Addr(addr)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(stack, expected, "{name}: stack");
+        assert_eq!(data.ancillary, f.ancillary, "{name}: ancillary");
+
+        assert!(!data.truncated, "{}: truncated", name);
+        assert!(!data.items_len_mismatch, "{}: items length", name);
+        assert!(!data.degraded, "{}: degraded", name);
+    }
+}
+
+#[test]
+fn all_zero() {
+    assert!(decode("all-zero.bin").is_none());
+}
+
+#[test]
+fn hubris1554_matches_intact() {
+    let intact = decode("v2-trap.bin").unwrap();
+    let fixed = decode("v2-hubris1554.bin").unwrap();
+
+    assert_eq!(fixed.raw_cause, intact.raw_cause);
+    assert_eq!(fixed.registers, intact.registers);
+    assert_eq!(fixed.raw_registers, intact.raw_registers);
+    assert_eq!(fixed.stack, intact.stack);
+}